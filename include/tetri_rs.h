@@ -0,0 +1,93 @@
+/* c interface to the tetri-rs engine, link against the cdylib built by `cargo build`
+ * (target/<profile>/libtetri_rs.so, .dylib or .dll).
+ *
+ * games are opaque handles. every call returns a status and never panics across the
+ * boundary, outputs are only written on TETRI_OK. */
+#ifndef TETRI_RS_H
+#define TETRI_RS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* compare with tetri_abi_version() before using anything else. */
+#define TETRI_ABI_VERSION 1
+
+typedef enum TetriStatus {
+    TETRI_OK = 0,
+    TETRI_NULL_POINTER = 1,
+    TETRI_INVALID_ARGUMENT = 2,
+    TETRI_GAME_OVER = 3,
+    TETRI_BUFFER_TOO_SMALL = 4,
+    TETRI_PANIC = 5,
+} TetriStatus;
+
+enum {
+    TETRI_INPUT_MOVE_LEFT = 0,
+    TETRI_INPUT_MOVE_RIGHT = 1,
+    TETRI_INPUT_DAS_LEFT = 2,
+    TETRI_INPUT_DAS_RIGHT = 3,
+    TETRI_INPUT_SOFT_DROP = 4,
+    TETRI_INPUT_HARD_DROP = 5,
+    TETRI_INPUT_ROTATE_CW = 6,
+    TETRI_INPUT_ROTATE_CCW = 7,
+    TETRI_INPUT_ROTATE_180 = 8,
+    TETRI_INPUT_HOLD = 9,
+};
+
+/* board cells and piece kinds. */
+enum {
+    TETRI_CELL_EMPTY = 0,
+    TETRI_PIECE_I = 1,
+    TETRI_PIECE_O = 2,
+    TETRI_PIECE_T = 3,
+    TETRI_PIECE_S = 4,
+    TETRI_PIECE_Z = 5,
+    TETRI_PIECE_J = 6,
+    TETRI_PIECE_L = 7,
+    TETRI_CELL_GARBAGE = 8,
+};
+
+typedef struct TetriGame TetriGame;
+
+typedef struct TetriState {
+    int32_t score;
+    uint32_t lines;
+    uint32_t pieces;
+    uint8_t current;
+    uint8_t next;
+    uint8_t hold; /* TETRI_CELL_EMPTY when nothing is held */
+    uint8_t game_over;
+} TetriState;
+
+/* the falling piece, rows count down from the top. */
+typedef struct TetriPiece {
+    uint8_t kind;
+    uint32_t rows[4];
+    uint32_t cols[4];
+} TetriPiece;
+
+uint32_t tetri_abi_version(void);
+
+/* width 4 to 30, height 10 to 40. free the game with tetri_game_free. */
+TetriStatus tetri_game_new(uint64_t seed, uint32_t width, uint32_t height, TetriGame **out);
+void tetri_game_free(TetriGame *game);
+
+TetriStatus tetri_game_input(TetriGame *game, uint32_t input);
+/* lets millis of gravity pass. */
+TetriStatus tetri_game_tick(TetriGame *game, uint64_t millis);
+
+TetriStatus tetri_game_size(const TetriGame *game, uint32_t *width, uint32_t *height);
+/* width * height cells row by row from the top, without the falling piece. */
+TetriStatus tetri_game_board(const TetriGame *game, uint8_t *cells, size_t len);
+TetriStatus tetri_game_state(const TetriGame *game, TetriState *state);
+TetriStatus tetri_game_piece(const TetriGame *game, TetriPiece *piece);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
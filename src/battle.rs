@@ -0,0 +1,326 @@
+use std::io::Stdout;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use crossterm::event::{poll, read, Event, KeyEventKind};
+use crossterm::terminal::size as terminal_size;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Deserialize;
+
+use crate::bindings::{Action, Bindings};
+use crate::bot::{Bot, BotConfig, Features, Heuristic, Weights};
+use crate::config::Config;
+use crate::game::Game;
+use crate::render::{mini_board_width, render_frames, render_garbage_meter, render_grid, render_message, render_mini_board, render_panel, Layout};
+use crate::terminal::TerminalGuard;
+use crate::theme::Theme;
+
+// keeps the opponents' noise and the targeting apart from the pieces.
+const BATTLE_SEED: u64 = 0xba77_1e5e_ed00_0001;
+
+// how strong an opponent plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+    // the bot from [bot], built-in or external, as configured there.
+    External,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+            Difficulty::External => "external",
+        }
+    }
+
+    // harder opponents play faster, hold, go for t-spins and tetrises and misjudge the board less.
+    pub fn bot_config(&self, base: &BotConfig) -> BotConfig {
+        let simple = Weights {
+            t_slots: 0.0,
+            tetris: 0.0,
+            t_spin: 0.0,
+            ..Weights::default()
+        };
+        let (pps, hold, weights, noise) = match self {
+            Difficulty::Easy => (0.5, false, simple, 2.0),
+            Difficulty::Medium => (1.0, true, simple, 1.0),
+            Difficulty::Hard => (2.0, true, Weights::default(), 0.25),
+            Difficulty::Expert => (3.5, true, Weights::default(), 0.0),
+            Difficulty::External => return base.clone(),
+        };
+        BotConfig {
+            pps,
+            hold,
+            weights,
+            external: None,
+            noise,
+            ..base.clone()
+        }
+    }
+
+    fn bot(&self, base: &BotConfig, seed: u64) -> Result<Bot, String> {
+        let config = self.bot_config(base);
+        match config.external {
+            Some(_) => Bot::new(&config),
+            None => Ok(Bot::with_brain(Box::new(Heuristic::with_seed(&config, seed)), config.pps)),
+        }
+    }
+}
+
+// who an attack goes to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Targeting {
+    #[default]
+    Random,
+    // someone attacking you, anyone when nobody is.
+    Attackers,
+    // whoever is closest to topping out.
+    Ko,
+}
+
+// the [battle] table.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BattleConfig {
+    pub opponents: Vec<Difficulty>,
+    pub targeting: Targeting,
+}
+
+impl Default for BattleConfig {
+    fn default() -> Self {
+        BattleConfig {
+            opponents: vec![Difficulty::Easy, Difficulty::Medium, Difficulty::Hard],
+            targeting: Targeting::default(),
+        }
+    }
+}
+
+pub struct Opponent {
+    pub difficulty: Difficulty,
+    pub game: Game,
+    pub bot: Bot,
+}
+
+// the player against bots, every board attacks the others by the same targeting.
+pub struct Battle {
+    pub player: Game,
+    pub opponents: Vec<Opponent>,
+    targeting: Targeting,
+    // per board, the player first: who it attacked last and who attacked it last.
+    targets: Vec<Option<usize>>,
+    attackers: Vec<Option<usize>>,
+    knocked_out: Vec<bool>,
+    kos: Vec<u32>,
+    rng: StdRng,
+}
+
+impl Battle {
+    // the player gets `seed`, each opponent the next one along.
+    pub fn new(config: &Config, seed: u64) -> Result<Battle, String> {
+        let mut opponents = vec![];
+        for (index, difficulty) in config.battle.opponents.iter().enumerate() {
+            let seed = seed.wrapping_add(1 + index as u64);
+            opponents.push(Opponent {
+                difficulty: *difficulty,
+                game: Game::new(&config.ruleset, seed),
+                bot: difficulty.bot(&config.bot, seed ^ BATTLE_SEED)?,
+            });
+        }
+        let boards = opponents.len() + 1;
+        let mut player = Game::new(&config.ruleset, seed);
+        player.finesse = true;
+        Ok(Battle {
+            player,
+            opponents,
+            targeting: config.battle.targeting,
+            targets: vec![None; boards],
+            attackers: vec![None; boards],
+            knocked_out: vec![false; boards],
+            kos: vec![0; boards],
+            rng: StdRng::seed_from_u64(seed ^ BATTLE_SEED),
+        })
+    }
+
+    fn game(&self, index: usize) -> &Game {
+        match index {
+            0 => &self.player,
+            _ => &self.opponents[index - 1].game,
+        }
+    }
+
+    fn game_mut(&mut self, index: usize) -> &mut Game {
+        match index {
+            0 => &mut self.player,
+            _ => &mut self.opponents[index - 1].game,
+        }
+    }
+
+    // the opponent the player attacked last, 0 for the first one.
+    pub fn player_target(&self) -> Option<usize> {
+        self.targets[0].map(|index| index - 1)
+    }
+
+    // opponents the player knocked out.
+    pub fn kos(&self) -> u32 {
+        self.kos[0]
+    }
+
+    pub fn opponents_left(&self) -> usize {
+        self.opponents.iter().filter(|opponent| !opponent.game.game_over).count()
+    }
+
+    // where the player finished, 1 for the last one standing.
+    pub fn place(&self) -> usize {
+        match self.player.game_over {
+            true => self.opponents_left() + 1,
+            false => 1,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.player.game_over || self.opponents_left() == 0
+    }
+
+    // moves every board on by `millis`, the bots press their inputs first.
+    pub fn tick(&mut self, millis: u64) {
+        self.player.tick(millis);
+        for opponent in &mut self.opponents {
+            opponent.bot.play(&mut opponent.game, millis);
+            opponent.game.tick(millis);
+        }
+        self.route_garbage();
+    }
+
+    // sends what each board cleared to its target and credits the knockouts.
+    fn route_garbage(&mut self) {
+        for from in 0..self.knocked_out.len() {
+            let sent = self.game_mut(from).take_sent();
+            if sent == 0 {
+                continue;
+            }
+            if let Some(to) = self.pick_target(from) {
+                self.targets[from] = Some(to);
+                self.attackers[to] = Some(from);
+                self.game_mut(to).receive(sent);
+            }
+        }
+        for index in 0..self.knocked_out.len() {
+            if self.game(index).game_over && !self.knocked_out[index] {
+                self.knocked_out[index] = true;
+                if let Some(by) = self.attackers[index] {
+                    self.kos[by] += 1;
+                }
+            }
+        }
+    }
+
+    fn pick_target(&mut self, from: usize) -> Option<usize> {
+        let alive: Vec<usize> = (0..self.knocked_out.len())
+            .filter(|index| *index != from && !self.game(*index).game_over)
+            .collect();
+        match self.targeting {
+            Targeting::Random => alive.choose(&mut self.rng).copied(),
+            Targeting::Attackers => {
+                let attacking: Vec<usize> = alive.iter().copied().filter(|index| self.targets[*index] == Some(from)).collect();
+                attacking.choose(&mut self.rng).or_else(|| alive.choose(&mut self.rng)).copied()
+            }
+            Targeting::Ko => alive.into_iter().max_by_key(|index| {
+                let game = self.game(*index);
+                // the first of equally close boards.
+                (Features::of(&game.grid).max_height + game.garbage.total(), std::cmp::Reverse(*index))
+            }),
+        }
+    }
+}
+
+// the player's board and the opponents drawn small beside it, as many as fit.
+pub fn run(stdout: &mut Stdout, terminal: &TerminalGuard, theme: &Theme, config: &Config, bindings: &Bindings, battle: &mut Battle) {
+    let ruleset = &config.ruleset;
+    let duration = Duration::from_millis(config.handling.frame_ms);
+    let (term_cols, term_rows) = terminal_size().unwrap();
+    let (mut layout, mut shown) = battle_layout(term_cols, term_rows, ruleset.height, ruleset.width, battle.opponents.len());
+    render_frames(stdout, theme, &[layout]);
+    let mut last_tick = Instant::now();
+
+    loop {
+        if terminal.quit_requested() {
+            return;
+        }
+        let game = &battle.player;
+        render_panel(stdout, theme, &layout, game);
+        render_grid(stdout, theme, &layout, &game.grid, &game.cur_tetris, None);
+        render_garbage_meter(stdout, theme, &layout, game.garbage.total());
+        let fits = layout.grid_y as usize + ruleset.height + 2 <= layout.term_rows as usize;
+        if !layout.too_small && fits {
+            let mini_width = mini_board_width(ruleset.width) + 1;
+            for (index, opponent) in battle.opponents.iter().take(shown).enumerate() {
+                let x = layout.frame_x + layout.frame_width + 1 + index as u16 * mini_width;
+                let label = match (opponent.game.game_over, battle.player_target() == Some(index)) {
+                    (true, _) => "KO".to_string(),
+                    (false, true) => format!(">{}", opponent.difficulty.name()),
+                    (false, false) => opponent.difficulty.name().to_string(),
+                };
+                render_mini_board(stdout, theme, x, layout.grid_y, &label, &opponent.game.board());
+            }
+        }
+        if battle.finished() {
+            let result = match battle.player.game_over {
+                true => "G A M E  O V E R",
+                false => "W I N S",
+            };
+            render_message(stdout, theme, &layout, &[result, "", "press any key"]);
+            terminal.wait_for_key();
+            return;
+        }
+
+        if poll(duration).unwrap() {
+            match read().unwrap() {
+                Event::Resize(term_cols, term_rows) => {
+                    (layout, shown) = battle_layout(term_cols, term_rows, ruleset.height, ruleset.width, battle.opponents.len());
+                    render_frames(stdout, theme, &[layout]);
+                }
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => match bindings.action(&key_event) {
+                    Some(Action::Quit) => return,
+                    Some(action) if !layout.too_small => {
+                        if let Some(input) = action.input() {
+                            battle.player.input(input);
+                        }
+                    }
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+
+        // everyone waits while the player cannot see the board.
+        let elapsed_millis = last_tick.elapsed().as_millis() as u64;
+        last_tick += Duration::from_millis(elapsed_millis);
+        if !layout.too_small {
+            battle.tick(elapsed_millis);
+        }
+    }
+}
+
+// the frame with room for as many of the `opponents` mini boards as the terminal allows.
+fn battle_layout(term_cols: u16, term_rows: u16, num_rows: usize, num_cols: usize, opponents: usize) -> (Layout, usize) {
+    let mini_width = mini_board_width(num_cols) + 1;
+    let mut shown = opponents;
+    loop {
+        let layout = Layout::with_reserved(term_cols, term_rows, num_rows, num_cols, shown as u16 * mini_width);
+        if !layout.too_small || shown == 0 {
+            return (layout, shown);
+        }
+        shown -= 1;
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::game::Input;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    DasLeft,
+    DasRight,
+    SoftDrop,
+    HardDrop,
+    RotateCw,
+    RotateCcw,
+    #[serde(rename = "rotate_180")]
+    Rotate180,
+    Hold,
+    ToggleHint,
+    // copy the board out as a fumen string.
+    Fumen,
+    Rebind,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::DasLeft,
+        Action::DasRight,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::RotateCw,
+        Action::RotateCcw,
+        Action::Rotate180,
+        Action::Hold,
+        Action::ToggleHint,
+        Action::Fumen,
+        Action::Rebind,
+        Action::Quit,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move left",
+            Action::MoveRight => "move right",
+            Action::DasLeft => "das left",
+            Action::DasRight => "das right",
+            Action::SoftDrop => "soft drop",
+            Action::HardDrop => "hard drop",
+            Action::RotateCw => "rotate cw",
+            Action::RotateCcw => "rotate ccw",
+            Action::Rotate180 => "rotate 180",
+            Action::Hold => "hold",
+            Action::ToggleHint => "toggle hint",
+            Action::Fumen => "fumen",
+            Action::Rebind => "rebind keys",
+            Action::Quit => "quit",
+        }
+    }
+
+    // the game input behind an action, if it is one.
+    pub fn input(&self) -> Option<Input> {
+        match self {
+            Action::MoveLeft => Some(Input::MoveLeft),
+            Action::MoveRight => Some(Input::MoveRight),
+            Action::DasLeft => Some(Input::DasLeft),
+            Action::DasRight => Some(Input::DasRight),
+            Action::SoftDrop => Some(Input::SoftDrop),
+            Action::HardDrop => Some(Input::HardDrop),
+            Action::RotateCw => Some(Input::RotateCw),
+            Action::RotateCcw => Some(Input::RotateCcw),
+            Action::Rotate180 => Some(Input::Rotate180),
+            Action::Hold => Some(Input::Hold),
+            Action::ToggleHint | Action::Fumen | Action::Rebind | Action::Quit => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Vim,
+    Arrows,
+    Wasd,
+    // two players on one keyboard, one set per hand.
+    #[serde(rename = "shared-left")]
+    SharedLeft,
+    #[serde(rename = "shared-right")]
+    SharedRight,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 5] = [Preset::Vim, Preset::Arrows, Preset::Wasd, Preset::SharedLeft, Preset::SharedRight];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Vim => "vim",
+            Preset::Arrows => "arrows",
+            Preset::Wasd => "wasd",
+            Preset::SharedLeft => "shared-left",
+            Preset::SharedRight => "shared-right",
+        }
+    }
+
+    fn keys(&self) -> Vec<(Action, &'static str)> {
+        let mut keys = match self {
+            Preset::Vim => vec![
+                (Action::MoveLeft, "h"),
+                (Action::MoveRight, "l"),
+                (Action::DasLeft, "shift+h"),
+                (Action::DasRight, "shift+l"),
+                (Action::SoftDrop, "j"),
+                (Action::HardDrop, "space"),
+                (Action::RotateCw, "k"),
+                (Action::RotateCcw, "z"),
+                (Action::Rotate180, "a"),
+                (Action::Hold, "c"),
+                (Action::Quit, "q"),
+            ],
+            Preset::Arrows => vec![
+                (Action::MoveLeft, "left"),
+                (Action::MoveRight, "right"),
+                (Action::DasLeft, "shift+left"),
+                (Action::DasRight, "shift+right"),
+                (Action::SoftDrop, "down"),
+                (Action::HardDrop, "space"),
+                (Action::RotateCw, "up"),
+                (Action::RotateCw, "x"),
+                (Action::RotateCcw, "z"),
+                (Action::Rotate180, "a"),
+                (Action::Hold, "c"),
+                (Action::Quit, "q"),
+            ],
+            Preset::Wasd => vec![
+                (Action::MoveLeft, "a"),
+                (Action::MoveRight, "d"),
+                (Action::DasLeft, "shift+a"),
+                (Action::DasRight, "shift+d"),
+                (Action::SoftDrop, "s"),
+                (Action::HardDrop, "space"),
+                (Action::RotateCw, "w"),
+                (Action::RotateCcw, "q"),
+                (Action::Rotate180, "r"),
+                (Action::Hold, "e"),
+                (Action::Quit, "esc"),
+            ],
+            Preset::SharedLeft => vec![
+                (Action::MoveLeft, "a"),
+                (Action::MoveRight, "d"),
+                (Action::DasLeft, "shift+a"),
+                (Action::DasRight, "shift+d"),
+                (Action::SoftDrop, "s"),
+                (Action::HardDrop, "w"),
+                (Action::RotateCw, "e"),
+                (Action::RotateCcw, "q"),
+                (Action::Rotate180, "r"),
+                (Action::Hold, "c"),
+                (Action::Quit, "esc"),
+            ],
+            Preset::SharedRight => vec![
+                (Action::MoveLeft, "left"),
+                (Action::MoveRight, "right"),
+                (Action::DasLeft, "shift+left"),
+                (Action::DasRight, "shift+right"),
+                (Action::SoftDrop, "down"),
+                (Action::HardDrop, "enter"),
+                (Action::RotateCw, "up"),
+                (Action::RotateCcw, "/"),
+                (Action::Rotate180, "."),
+                (Action::Hold, ","),
+                (Action::Quit, "backspace"),
+            ],
+        };
+        keys.push((Action::Rebind, "f2"));
+        keys.push((Action::ToggleHint, "f3"));
+        keys.push((Action::Fumen, "f4"));
+        keys
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    // shift is folded into the char, so `H` and `shift+h` are the same key.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> KeyBinding {
+        match code {
+            KeyCode::Char(ch) if modifiers.contains(KeyModifiers::SHIFT) => KeyBinding {
+                code: KeyCode::Char(ch.to_ascii_uppercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => KeyBinding { code, modifiers },
+        }
+    }
+
+    pub fn from_event(event: &KeyEvent) -> KeyBinding {
+        KeyBinding::new(event.code, event.modifiers)
+    }
+
+    // raw mode swallows the interrupt, so ctrl+c always quits and is kept out
+    // of the map where a rebind could take it away.
+    pub const INTERRUPT: KeyBinding = KeyBinding {
+        code: KeyCode::Char('c'),
+        modifiers: KeyModifiers::CONTROL,
+    };
+
+    pub fn is_interrupt(&self) -> bool {
+        *self == KeyBinding::INTERRUPT
+    }
+
+    // "h", "shift+left", "ctrl+c", "space", "f2" ...
+    pub fn parse(text: &str) -> Result<KeyBinding, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = text.split('+').collect();
+        // a lone "+" or a trailing "ctrl++" means the plus key itself.
+        let key = match parts.pop() {
+            Some("") if text.ends_with('+') => {
+                parts.pop();
+                "+"
+            }
+            Some(key) => key,
+            None => return Err(format!("empty key {:?}", text)),
+        };
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {:?} in {:?}", part, text)),
+            };
+        }
+
+        let code = match key.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            lower => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => KeyCode::Char(ch),
+                    _ => match lower.strip_prefix('f').and_then(|num| num.parse::<u8>().ok()) {
+                        Some(num) if (1..=24).contains(&num) => KeyCode::F(num),
+                        _ => return Err(format!("unknown key {:?}", text)),
+                    },
+                }
+            }
+        };
+        Ok(KeyBinding::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        let key = match self.code {
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char(ch) => ch.to_string(),
+            KeyCode::F(num) => format!("f{}", num),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::BackTab => "backtab".to_string(),
+            KeyCode::Backspace => "backspace".to_string(),
+            KeyCode::Delete => "delete".to_string(),
+            KeyCode::Insert => "insert".to_string(),
+            KeyCode::Home => "home".to_string(),
+            KeyCode::End => "end".to_string(),
+            KeyCode::PageUp => "pageup".to_string(),
+            KeyCode::PageDown => "pagedown".to_string(),
+            KeyCode::Left => "left".to_string(),
+            KeyCode::Right => "right".to_string(),
+            KeyCode::Up => "up".to_string(),
+            KeyCode::Down => "down".to_string(),
+            other => format!("{:?}", other).to_ascii_lowercase(),
+        };
+        write!(f, "{}", key)
+    }
+}
+
+// one key or a list of keys for an action.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    fn keys(&self) -> Vec<String> {
+        match self {
+            KeyList::One(key) => vec![key.clone()],
+            KeyList::Many(keys) => keys.clone(),
+        }
+    }
+}
+
+// the [bindings] table of the config file, `keys` replaces the preset's keys per action.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BindingsConfig {
+    pub preset: Preset,
+    pub keys: BTreeMap<Action, KeyList>,
+}
+
+pub struct Bindings {
+    pub preset: Preset,
+    map: HashMap<KeyBinding, Action>,
+}
+
+impl Bindings {
+    pub fn from_preset(preset: Preset) -> Bindings {
+        let mut bindings = Bindings {
+            preset,
+            map: HashMap::new(),
+        };
+        for (action, key) in preset.keys() {
+            bindings.map.insert(KeyBinding::parse(key).unwrap(), action);
+        }
+        bindings
+    }
+
+    pub fn from_config(bindings_config: &BindingsConfig) -> Result<Bindings, String> {
+        let mut bindings = Bindings::from_preset(bindings_config.preset);
+        for (action, key_list) in &bindings_config.keys {
+            let mut keys = vec![];
+            for key in key_list.keys() {
+                let key = KeyBinding::parse(&key).map_err(|err| format!("bindings, {}: {}", action.name(), err))?;
+                if key.is_interrupt() && *action != Action::Quit {
+                    return Err(format!("bindings, {}: ctrl+c always quits and can't be rebound", action.name()));
+                }
+                keys.push(key);
+            }
+            bindings.set_keys(*action, keys);
+        }
+        Ok(bindings)
+    }
+
+    pub fn to_config(&self) -> BindingsConfig {
+        let mut keys = BTreeMap::new();
+        for action in Action::ALL {
+            let key_list = self.keys_for(action).iter().map(|key| key.to_string()).collect();
+            keys.insert(action, KeyList::Many(key_list));
+        }
+        BindingsConfig {
+            preset: self.preset,
+            keys,
+        }
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let key = KeyBinding::from_event(event);
+        match key.is_interrupt() {
+            true => Some(Action::Quit),
+            false => self.map.get(&key).copied(),
+        }
+    }
+
+    pub fn keys_for(&self, action: Action) -> Vec<KeyBinding> {
+        let mut keys: Vec<KeyBinding> = self.map
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| *key)
+            .collect();
+        if action == Action::Quit {
+            keys.push(KeyBinding::INTERRUPT);
+        }
+        keys.sort_by_key(|key| key.to_string());
+        keys
+    }
+
+    // replaces every key of `action`, stealing the keys from other actions,
+    // ctrl+c is never in the map so it stays on quit.
+    pub fn set_keys(&mut self, action: Action, keys: Vec<KeyBinding>) {
+        self.map.retain(|_, bound| *bound != action);
+        for key in keys.into_iter().filter(|key| !key.is_interrupt()) {
+            self.map.insert(key, action);
+        }
+    }
+}
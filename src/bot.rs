@@ -0,0 +1,303 @@
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::game::{Game, Input};
+use crate::models::grid::Grid;
+use crate::models::tetris::Tetris;
+use crate::movegen::{self, Placement};
+use crate::tbp::TbpBrain;
+
+// how much each board feature is worth, positive is good.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weights {
+    pub aggregate_height: f64,
+    pub max_height: f64,
+    pub holes: f64,
+    pub bumpiness: f64,
+    pub wells: f64,
+    pub t_slots: f64,
+    pub lines: f64,
+    pub tetris: f64,
+    pub t_spin: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            aggregate_height: -0.51,
+            max_height: -0.3,
+            holes: -3.6,
+            bumpiness: -0.18,
+            wells: -0.2,
+            t_slots: 1.0,
+            lines: 0.2,
+            tetris: 8.0,
+            t_spin: 6.0,
+        }
+    }
+}
+
+// the [bot] table of the config file.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotConfig {
+    // pieces per second, the inputs of each piece are spread across its time.
+    pub pps: f64,
+    pub hold: bool,
+    pub weights: Weights,
+    // command and arguments of a bot speaking the tetris bot protocol, replaces the built-in one.
+    pub external: Option<Vec<String>>,
+    // how long an external bot gets to suggest a move.
+    pub move_ms: u64,
+    // up to this much is added to or taken off each placement's score, weakens the built-in bot.
+    pub noise: f64,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        BotConfig {
+            pps: 2.0,
+            hold: true,
+            weights: Weights::default(),
+            external: None,
+            move_ms: 1000,
+            noise: 0.0,
+        }
+    }
+}
+
+// decides where each piece goes, the bot then presses the inputs.
+pub trait Brain {
+    fn choose(&mut self, game: &Game) -> Result<Vec<Input>, String>;
+}
+
+// the built-in brain, scores every reachable placement with the weights.
+pub struct Heuristic {
+    weights: Weights,
+    hold: bool,
+    noise: f64,
+    rng: StdRng,
+}
+
+impl Heuristic {
+    pub fn new(config: &BotConfig) -> Heuristic {
+        Heuristic::with_seed(config, rand::random())
+    }
+
+    // the noise comes from `seed`, so a seeded game plays out the same.
+    pub fn with_seed(config: &BotConfig, seed: u64) -> Heuristic {
+        Heuristic {
+            weights: config.weights.clone(),
+            hold: config.hold,
+            noise: config.noise,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Heuristic {
+    fn best_placement(&mut self, grid: &Grid, tetris: &Tetris) -> Option<(f64, Placement)> {
+        if self.noise == 0.0 {
+            return best_placement(grid, tetris, &self.weights);
+        }
+        let (weights, noise, rng) = (&self.weights, self.noise, &mut self.rng);
+        best_placement_by(grid, tetris, |placement| evaluate(grid, placement, weights) + rng.gen_range(-noise..=noise))
+    }
+}
+
+impl Brain for Heuristic {
+    // the inputs for the best placement of the current piece, or of the held one.
+    fn choose(&mut self, game: &Game) -> Result<Vec<Input>, String> {
+        let mut best = self.best_placement(&game.grid, &game.cur_tetris)
+            .map(|(score, placement)| (score, placement.inputs));
+        if self.hold {
+            let held = game.saved_tetris.as_ref().unwrap_or(&game.next_tetris);
+            if let Some((score, placement)) = self.best_placement(&game.grid, held) {
+                if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+                    let mut inputs = vec![Input::Hold];
+                    inputs.extend(placement.inputs);
+                    best = Some((score, inputs));
+                }
+            }
+        }
+        best.map(|(_, inputs)| inputs).ok_or_else(|| "no placement left".to_string())
+    }
+}
+
+// plays a game through the same inputs a player would press.
+pub struct Bot {
+    brain: Box<dyn Brain>,
+    pps: f64,
+    plan: VecDeque<Input>,
+    // pieces locked when the plan was made, a lock mid plan makes it stale.
+    plan_pieces: u32,
+    input_ms: f64,
+    wait_ms: f64,
+    // why the bot gave up, the game is over once this is set.
+    pub error: Option<String>,
+}
+
+impl Bot {
+    // the external bot when one is configured, the built-in one otherwise.
+    pub fn new(config: &BotConfig) -> Result<Bot, String> {
+        let brain: Box<dyn Brain> = match &config.external {
+            Some(command) => Box::new(TbpBrain::spawn(command, config.move_ms)?),
+            None => Box::new(Heuristic::new(config)),
+        };
+        Ok(Bot::with_brain(brain, config.pps))
+    }
+
+    pub fn with_brain(brain: Box<dyn Brain>, pps: f64) -> Bot {
+        Bot {
+            brain,
+            pps,
+            plan: VecDeque::new(),
+            plan_pieces: 0,
+            input_ms: 0.0,
+            wait_ms: 0.0,
+            error: None,
+        }
+    }
+
+    // presses every input due in the next `millis`, planning a new piece once the last is placed.
+    pub fn play(&mut self, game: &mut Game, millis: u64) {
+        self.wait_ms -= millis as f64;
+        while self.wait_ms <= 0.0 && !game.game_over {
+            if game.stats.pieces != self.plan_pieces {
+                self.plan.clear();
+            }
+            if self.plan.is_empty() {
+                let inputs = match self.brain.choose(game) {
+                    Ok(inputs) if !inputs.is_empty() => inputs,
+                    Ok(_) => return self.give_up(game, "empty move".to_string()),
+                    Err(err) => return self.give_up(game, err),
+                };
+                self.input_ms = 1000.0 / self.pps / inputs.len() as f64;
+                self.plan = inputs.into();
+                self.plan_pieces = game.stats.pieces;
+            }
+            let input = self.plan.pop_front().unwrap();
+            // gravity may have done some of the soft dropping already, and a blocked soft drop locks.
+            let can_fall = game.cur_tetris.clone().try_move_or_set_tetris(&game.grid.grid_vec, &(1, 0));
+            if input != Input::SoftDrop || can_fall {
+                game.input(input);
+                self.wait_ms += self.input_ms;
+            }
+        }
+    }
+
+    fn give_up(&mut self, game: &mut Game, err: String) {
+        self.error = Some(err);
+        game.game_over = true;
+    }
+}
+
+// where the bot would put the current piece, for the hint overlay.
+pub fn suggest(game: &Game, weights: &Weights) -> Option<Tetris> {
+    best_placement(&game.grid, &game.cur_tetris, weights).map(|(_, placement)| placement.tetris)
+}
+
+pub fn best_placement(grid: &Grid, tetris: &Tetris, weights: &Weights) -> Option<(f64, Placement)> {
+    best_placement_by(grid, tetris, |placement| evaluate(grid, placement, weights))
+}
+
+// the highest scoring placement, soft drops included.
+fn best_placement_by(grid: &Grid, tetris: &Tetris, mut score_of: impl FnMut(&Placement) -> f64) -> Option<(f64, Placement)> {
+    let mut best: Option<(f64, Placement)> = None;
+    for placement in movegen::placements(grid, tetris, true) {
+        let score = score_of(&placement);
+        if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+            best = Some((score, placement));
+        }
+    }
+    best
+}
+
+// scores the board left behind by a placement.
+pub fn evaluate(grid: &Grid, placement: &Placement, weights: &Weights) -> f64 {
+    let mut after = Grid {
+        grid_vec: grid.grid_vec.clone(),
+    };
+    let lines = after.apply_tetris(&placement.tetris);
+    let features = Features::of(&after);
+
+    let mut score = weights.aggregate_height * features.aggregate_height as f64
+        + weights.max_height * features.max_height as f64
+        + weights.holes * features.holes as f64
+        + weights.bumpiness * features.bumpiness as f64
+        + weights.wells * features.wells as f64
+        + weights.t_slots * features.t_slots as f64
+        + weights.lines * lines as f64;
+    if lines >= 4 {
+        score += weights.tetris;
+    }
+    if placement.t_spin && lines > 0 {
+        score += weights.t_spin * lines as f64;
+    }
+    score
+}
+
+#[derive(Default)]
+pub struct Features {
+    pub aggregate_height: u32,
+    pub max_height: u32,
+    pub holes: u32,
+    pub bumpiness: u32,
+    // depth of every column lower than both neighbours, walls count as high.
+    pub wells: u32,
+    // spots a t could spin into for a double.
+    pub t_slots: u32,
+}
+
+impl Features {
+    pub fn of(grid: &Grid) -> Features {
+        let rows = &grid.grid_vec;
+        let num_rows = rows.len();
+        let num_cols = rows.first().map_or(0, |row| row.len());
+        let filled = |row: usize, col: usize| !rows[row][col].is_empty();
+
+        let heights: Vec<u32> = (0..num_cols)
+            .map(|col| match (0..num_rows).find(|row| filled(*row, col)) {
+                Some(top) => (num_rows - top) as u32,
+                None => 0,
+            })
+            .collect();
+
+        let mut features = Features {
+            aggregate_height: heights.iter().sum(),
+            max_height: heights.iter().copied().max().unwrap_or(0),
+            ..Features::default()
+        };
+        for col in 0..num_cols {
+            let top = num_rows - heights[col] as usize;
+            features.holes += (top..num_rows).filter(|row| !filled(*row, col)).count() as u32;
+            if col + 1 < num_cols {
+                features.bumpiness += heights[col].abs_diff(heights[col + 1]);
+            }
+            let left = if col == 0 { u32::MAX } else { heights[col - 1] };
+            let right = if col + 1 == num_cols { u32::MAX } else { heights[col + 1] };
+            let wall = left.min(right);
+            if wall != u32::MAX && wall > heights[col] {
+                features.wells += wall - heights[col];
+            }
+        }
+
+        // a t-slot: three open cells over one open cell with filled cells either side of it,
+        // and an overhang on one side to spin under.
+        for row in 1..num_rows.saturating_sub(1) {
+            for col in 1..num_cols.saturating_sub(1) {
+                let open_top = !filled(row, col - 1) && !filled(row, col) && !filled(row, col + 1);
+                let open_bottom = !filled(row + 1, col) && filled(row + 1, col - 1) && filled(row + 1, col + 1);
+                let overhang = filled(row - 1, col - 1) != filled(row - 1, col + 1) && !filled(row - 1, col);
+                if open_top && open_bottom && overhang {
+                    features.t_slots += 1;
+                }
+            }
+        }
+        features
+    }
+}
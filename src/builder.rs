@@ -1,19 +1,25 @@
 
 pub mod build {
+    use crate::models::cell::PieceKind;
     use crate::models::tetris::Tetris;
     use crate::models::tetris::RowCol;
     use rand::Rng;
 
-    pub fn build_random_tetris(origin_row: usize, origin_col: usize) -> Tetris {
-        let mut rng = rand::thread_rng();
-        let rand_num = rng.gen_range(0..=4);
-        match rand_num {
-            0 => build_square_tetris(origin_row, origin_col),
-            1 => build_tee_tetris(origin_row, origin_col),
-            2 => build_zaa_tetris(origin_row, origin_col),
-            3 => build_zee_tetris(origin_row, origin_col),
-            4 => build_long_tetris(origin_row, origin_col),
-            _ => build_zee_tetris(origin_row, origin_col),
+    pub fn build_random_tetris<R: Rng>(rng: &mut R, origin_row: usize, origin_col: usize) -> Tetris {
+        let kinds = [PieceKind::O, PieceKind::T, PieceKind::Z, PieceKind::S, PieceKind::I, PieceKind::J, PieceKind::L];
+        let rand_num = rng.gen_range(0..=6);
+        build_tetris(kinds[rand_num], origin_row, origin_col)
+    }
+
+    pub fn build_tetris(kind: PieceKind, origin_row: usize, origin_col: usize) -> Tetris {
+        match kind {
+            PieceKind::O => build_square_tetris(origin_row, origin_col),
+            PieceKind::T => build_tee_tetris(origin_row, origin_col),
+            PieceKind::Z => build_zaa_tetris(origin_row, origin_col),
+            PieceKind::S => build_zee_tetris(origin_row, origin_col),
+            PieceKind::I => build_long_tetris(origin_row, origin_col),
+            PieceKind::J => build_jay_tetris(origin_row, origin_col),
+            PieceKind::L => build_ell_tetris(origin_row, origin_col),
         }
     }
 
@@ -29,7 +35,7 @@ pub mod build {
             RowCol {row: origin_row + 1, col: origin_col + 1},
             ];
 
-        Tetris::new(poses, RowCol {row: 0, col: 0}, RowCol {row: origin_row, col: origin_col}, 0, 1)
+        Tetris::new(poses, RowCol {row: 0, col: 0}, RowCol {row: origin_row, col: origin_col}, 0, PieceKind::O)
     }
 
     // origin: o->[ ], centre: c->[ ]
@@ -43,7 +49,7 @@ pub mod build {
             RowCol {row: origin_row + 1, col: origin_col + 2},
             ];
 
-        Tetris::new(poses, RowCol {row: 1, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, 2)
+        Tetris::new(poses, RowCol {row: 1, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, PieceKind::T)
     }
 
     // origin: o->[ ], centre: c->[ ]
@@ -57,7 +63,7 @@ pub mod build {
             RowCol {row: origin_row + 1, col: origin_col + 1}, 
             ];
 
-        Tetris::new(poses, RowCol {row: 1, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, 5)
+        Tetris::new(poses, RowCol {row: 1, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, PieceKind::S)
     }
 
     // origin: o->[ ], centre: c->[ ]
@@ -71,7 +77,7 @@ pub mod build {
             RowCol {row: origin_row + 1, col: origin_col + 2},
             ];
 
-        Tetris::new(poses, RowCol {row: 0, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, 4)
+        Tetris::new(poses, RowCol {row: 0, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, PieceKind::Z)
     }
 
     // origin: o->[ ], centre: c->[ ]
@@ -85,6 +91,34 @@ pub mod build {
             RowCol {row: origin_row, col: origin_col + 3}, 
             ];
 
-        Tetris::new(poses, RowCol {row: 0, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, 6)
+        Tetris::new(poses, RowCol {row: 0, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, PieceKind::I)
+    }
+
+    // origin: o->[ ], centre: c->[ ]
+    // o[X], [ ], [ ]
+    //  [X],c[X], [X]
+    fn build_jay_tetris(origin_row: usize, origin_col: usize) -> Tetris {
+        let poses = vec![
+            RowCol {row: origin_row, col: origin_col}, 
+            RowCol {row: origin_row + 1, col: origin_col}, 
+            RowCol {row: origin_row + 1, col: origin_col + 1}, 
+            RowCol {row: origin_row + 1, col: origin_col + 2},
+            ];
+
+        Tetris::new(poses, RowCol {row: 1, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, PieceKind::J)
+    }
+
+    // origin: o->[ ], centre: c->[ ]
+    // o[ ], [ ], [X]
+    //  [X],c[X], [X]
+    fn build_ell_tetris(origin_row: usize, origin_col: usize) -> Tetris {
+        let poses = vec![
+            RowCol {row: origin_row, col: origin_col + 2}, 
+            RowCol {row: origin_row + 1, col: origin_col}, 
+            RowCol {row: origin_row + 1, col: origin_col + 1}, 
+            RowCol {row: origin_row + 1, col: origin_col + 2},
+            ];
+
+        Tetris::new(poses, RowCol {row: 1, col: 1}, RowCol {row: origin_row, col: origin_col}, 0, PieceKind::L)
     }
 }
\ No newline at end of file
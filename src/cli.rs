@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use serde::Deserialize;
+
+use crate::battle::{Difficulty, Targeting};
+use crate::bindings::Preset;
+use crate::config::{Config, Mode};
+use crate::opener::Order;
+use crate::theme::ColourDepth;
+
+// flags override the matching value from the config file.
+#[derive(Parser)]
+#[command(name = "tetri-rs", version, about = "tetris in rust")]
+pub struct Cli {
+    /// config file to use instead of $XDG_CONFIG_HOME/tetri-rs/config.toml
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    #[arg(long)]
+    pub mode: Option<Mode>,
+
+    /// grid width in cells
+    #[arg(long)]
+    pub width: Option<usize>,
+
+    /// grid height in cells
+    #[arg(long)]
+    pub height: Option<usize>,
+
+    /// milliseconds between gravity drops
+    #[arg(long, value_name = "MS")]
+    pub drop_ms: Option<u64>,
+
+    /// seed for the piece randomizer, random if not set
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// milliseconds per frame
+    #[arg(long, value_name = "MS")]
+    pub frame_ms: Option<u64>,
+
+    /// put the piece back at the top after a finesse fault
+    #[arg(long)]
+    pub finesse_restart: bool,
+
+    /// show the suggested placement, toggled in game with f3
+    #[arg(long)]
+    pub hint: bool,
+
+    /// pieces per second for the bot
+    #[arg(long, value_name = "PPS")]
+    pub bot_pps: Option<f64>,
+
+    /// command line of a tetris bot protocol bot to play instead of the built-in one
+    #[arg(long, value_name = "COMMAND")]
+    pub external_bot: Option<String>,
+
+    /// wait for a player to join on this port or address
+    #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = "7777", conflicts_with = "join")]
+    pub host: Option<String>,
+
+    /// play against the player hosting at this host:port
+    #[arg(long, value_name = "HOST:PORT")]
+    pub join: Option<String>,
+
+    /// let viewers watch this game on this port or address
+    #[arg(long, value_name = "ADDR")]
+    pub spectate_port: Option<String>,
+
+    /// watch the game streamed from host:port
+    #[arg(long, value_name = "HOST:PORT", conflicts_with_all = ["host", "join", "spectate_port"])]
+    pub watch: Option<String>,
+
+    /// give both versus players the same pieces
+    #[arg(long)]
+    pub same_seed: bool,
+
+    /// difficulty of each battle opponent, comma separated
+    #[arg(long, value_name = "LEVELS", value_delimiter = ',')]
+    pub opponents: Option<Vec<Difficulty>>,
+
+    /// who battle attacks go to
+    #[arg(long)]
+    pub targeting: Option<Targeting>,
+
+    /// cheese lines to start the cheese and dig modes with
+    #[arg(long, value_name = "LINES")]
+    pub cheese_lines: Option<u32>,
+
+    /// opener to practise in the opener mode, built-in or from [opener.openers]
+    #[arg(long, value_name = "NAME")]
+    pub opener: Option<String>,
+
+    /// deal the opener's own queue or shuffled bags
+    #[arg(long)]
+    pub opener_order: Option<Order>,
+
+    /// paint a position, loading the named one if it exists
+    #[arg(long, value_name = "NAME", num_args = 0..=1, conflicts_with_all = ["host", "join", "watch"])]
+    pub edit: Option<Option<String>>,
+
+    /// start from a saved position, by name or path
+    #[arg(long, value_name = "NAME")]
+    pub position: Option<String>,
+
+    /// start from a fumen string or link, or edit it with --edit
+    #[arg(long, value_name = "FUMEN", conflicts_with = "position")]
+    pub fumen: Option<String>,
+
+    /// page of the fumen to start from
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "fumen")]
+    pub fumen_page: usize,
+
+    /// print the --position or --fumen board as a fumen string and exit
+    #[arg(long)]
+    pub export_fumen: bool,
+
+    /// colour theme, built-in or from [theme.themes]
+    #[arg(long, value_name = "NAME")]
+    pub theme: Option<String>,
+
+    /// auto, truecolor, 256, 16 or mono
+    #[arg(long, value_parser = parse_colour_depth)]
+    pub colour_depth: Option<ColourDepth>,
+
+    /// key binding preset: vim, arrows, wasd, shared-left or shared-right
+    #[arg(long, value_parser = parse_preset)]
+    pub preset: Option<Preset>,
+
+    /// write the end of game statistics to this file as json
+    #[arg(long, value_name = "PATH")]
+    pub stats_json: Option<PathBuf>,
+}
+
+impl Cli {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(mode) = self.mode {
+            config.mode = mode;
+        }
+        if let Some(width) = self.width {
+            config.ruleset.width = width;
+        }
+        if let Some(height) = self.height {
+            config.ruleset.height = height;
+        }
+        if let Some(drop_ms) = self.drop_ms {
+            config.ruleset.drop_ms = drop_ms;
+        }
+        if let Some(seed) = self.seed {
+            config.ruleset.seed = Some(seed);
+        }
+        if let Some(frame_ms) = self.frame_ms {
+            config.handling.frame_ms = frame_ms;
+        }
+        if self.finesse_restart {
+            config.handling.finesse_restart = true;
+        }
+        if self.hint {
+            config.handling.hint = true;
+        }
+        if let Some(bot_pps) = self.bot_pps {
+            config.bot.pps = bot_pps;
+        }
+        if let Some(command) = &self.external_bot {
+            config.bot.external = Some(command.split_whitespace().map(str::to_string).collect());
+        }
+        if self.same_seed {
+            config.versus.same_seed = true;
+        }
+        if let Some(opponents) = &self.opponents {
+            config.battle.opponents = opponents.clone();
+        }
+        if let Some(targeting) = self.targeting {
+            config.battle.targeting = targeting;
+        }
+        if let Some(lines) = self.cheese_lines {
+            config.dig.lines = lines;
+        }
+        if let Some(opener) = &self.opener {
+            config.opener.name = opener.clone();
+        }
+        if let Some(order) = self.opener_order {
+            config.opener.order = order;
+        }
+        if let Some(theme) = &self.theme {
+            config.theme.name = Some(theme.clone());
+        }
+        if let Some(colour_depth) = self.colour_depth {
+            config.theme.colour_depth = colour_depth;
+        }
+        if let Some(preset) = self.preset {
+            config.bindings.preset = preset;
+        }
+    }
+}
+
+// the config file spellings double as flag values.
+fn parse_colour_depth(value: &str) -> Result<ColourDepth, String> {
+    ColourDepth::deserialize(toml::Value::String(value.to_string()))
+        .map_err(|_| "expected one of auto, truecolor, 256, 16, mono".to_string())
+}
+
+fn parse_preset(value: &str) -> Result<Preset, String> {
+    Preset::deserialize(toml::Value::String(value.to_string()))
+        .map_err(|_| "expected one of vim, arrows, wasd, shared-left, shared-right".to_string())
+}
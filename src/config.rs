@@ -0,0 +1,184 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::battle::BattleConfig;
+use crate::bindings::BindingsConfig;
+use crate::bot::BotConfig;
+use crate::dig::DigConfig;
+use crate::garbage::GarbageRules;
+use crate::opener::OpenerConfig;
+use crate::theme::ThemeConfig;
+use crate::versus::VersusConfig;
+
+const APP_DIR_NAME: &str = "tetri-rs";
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+// the rules of the game itself, the same for every player.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ruleset {
+    pub width: usize,
+    pub height: usize,
+    pub drop_ms: u64,
+    pub base_score: i32,
+    pub tetris_multiplier: f64,
+    pub t_spin_multiplier: f64,
+    pub seed: Option<u64>,
+    pub garbage: GarbageRules,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset {
+            width: 10,
+            height: 20,
+            drop_ms: 300,
+            base_score: 1000,
+            tetris_multiplier: 1.5,
+            t_spin_multiplier: 2.0,
+            seed: None,
+            garbage: GarbageRules::default(),
+        }
+    }
+}
+
+// how the game feels to play, per player.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Handling {
+    pub frame_ms: u64,
+    // retry the piece from the top after a finesse fault.
+    pub finesse_restart: bool,
+    // start with the bot's suggested placement drawn.
+    pub hint: bool,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            frame_ms: 10,
+            finesse_restart: false,
+            hint: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Marathon,
+    // the bot plays on its own, for attract screens and stress tests.
+    Demo,
+    // two players side by side on one keyboard.
+    Versus,
+    // one player against bots.
+    Battle,
+    // clear the starting cheese as fast as possible.
+    Cheese,
+    // dig through cheese that keeps rising faster.
+    Dig,
+    // build an opener over and over from an empty board.
+    Opener,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub mode: Mode,
+    pub ruleset: Ruleset,
+    pub handling: Handling,
+    pub theme: ThemeConfig,
+    pub bindings: BindingsConfig,
+    pub bot: BotConfig,
+    pub versus: VersusConfig,
+    pub battle: BattleConfig,
+    pub dig: DigConfig,
+    pub opener: OpenerConfig,
+}
+
+impl Config {
+    // an explicit path has to exist, the default one is optional.
+    pub fn load(path: Option<&Path>) -> Result<Config, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_config_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) if !required => return Ok(Config::default()),
+            Err(err) => return Err(format!("{}: {}", path.display(), err)),
+        };
+        toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let ruleset = &self.ruleset;
+        check_range("ruleset.width", ruleset.width, 4, 30)?;
+        // the side panel needs at least 10 rows next to the grid.
+        check_range("ruleset.height", ruleset.height, 10, 40)?;
+        check_range("ruleset.drop_ms", ruleset.drop_ms, 1, 60_000)?;
+        check_range("ruleset.base_score", ruleset.base_score, 0, 1_000_000)?;
+        check_range("ruleset.tetris_multiplier", ruleset.tetris_multiplier, 0.0, 100.0)?;
+        check_range("ruleset.t_spin_multiplier", ruleset.t_spin_multiplier, 0.0, 100.0)?;
+        check_range("ruleset.garbage.messiness", ruleset.garbage.messiness, 0.0, 1.0)?;
+        check_range("ruleset.garbage.cap", ruleset.garbage.cap, 1, 40)?;
+        check_range("handling.frame_ms", self.handling.frame_ms, 1, 1000)?;
+        check_range("bot.pps", self.bot.pps, 0.1, 1000.0)?;
+        check_range("bot.move_ms", self.bot.move_ms, 1, 60_000)?;
+        check_range("bot.noise", self.bot.noise, 0.0, 1000.0)?;
+        if self.mode == Mode::Battle {
+            check_range("the number of battle.opponents", self.battle.opponents.len(), 1, 8)?;
+        }
+        // the pieces need a few rows above the cheese to spawn.
+        if matches!(self.mode, Mode::Cheese | Mode::Dig) {
+            check_range("dig.lines", self.dig.lines, 1, ruleset.height as u32 - 4)?;
+        }
+        check_range("dig.start_ms", self.dig.start_ms, 100, 60_000)?;
+        check_range("dig.min_ms", self.dig.min_ms, 100, 60_000)?;
+        check_range("dig.speedup", self.dig.speedup, 0.5, 1.0)?;
+        Ok(())
+    }
+}
+
+fn check_range<T: PartialOrd + std::fmt::Display>(name: &str, value: T, min: T, max: T) -> Result<(), String> {
+    if value < min || value > max {
+        return Err(format!("{} must be between {} and {}, got {}", name, min, max, value));
+    }
+    Ok(())
+}
+
+// $XDG_CONFIG_HOME/tetri-rs, falling back to ~/.config/tetri-rs.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join(APP_DIR_NAME));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join(APP_DIR_NAME))
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_FILE_NAME))
+}
+
+// rewrites only the [bindings] table, the rest of the file is kept as is.
+pub fn save_bindings(path: &Path, bindings_config: &BindingsConfig) -> Result<(), String> {
+    let mut table = match fs::read_to_string(path) {
+        Ok(text) => text.parse::<toml::Table>().map_err(|err| format!("{}: {}", path.display(), err))?,
+        Err(_) => toml::Table::new(),
+    };
+    let bindings = toml::Value::try_from(bindings_config).map_err(|err| err.to_string())?;
+    table.insert("bindings".to_string(), bindings);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+    }
+    let text = toml::to_string(&table).map_err(|err| format!("{}: {}", path.display(), err))?;
+    fs::write(path, text).map_err(|err| format!("{}: {}", path.display(), err))
+}
@@ -0,0 +1,109 @@
+use serde::Deserialize;
+
+use crate::game::Game;
+use crate::models::cell::Cell;
+
+// the [dig] table, for the cheese race and dig survival.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DigConfig {
+    // cheese lines the board starts with.
+    pub lines: u32,
+    // survival only: the first new line rises after `start_ms`, each one after it `speedup` times
+    // sooner, but never sooner than `min_ms`.
+    pub start_ms: u64,
+    pub min_ms: u64,
+    pub speedup: f64,
+}
+
+impl Default for DigConfig {
+    fn default() -> Self {
+        DigConfig {
+            lines: 10,
+            start_ms: 3000,
+            min_ms: 500,
+            speedup: 0.97,
+        }
+    }
+}
+
+// cheese to dig through, a race to clear it all or survival while more keeps coming.
+pub struct Dig {
+    survival: bool,
+    // cheese lines raised so far, the starting ones included.
+    added: u32,
+    // lines due that wait for the next piece to lock.
+    due: u32,
+    interval_ms: f64,
+    timer_ms: f64,
+    min_ms: f64,
+    speedup: f64,
+    pieces: u32,
+}
+
+impl Dig {
+    // a race: the board starts with the cheese and is done once all of it is cleared.
+    pub fn race(game: &mut Game, config: &DigConfig) -> Dig {
+        Dig::new(game, config, false)
+    }
+
+    // survival: the board starts with the cheese and a new line rises faster and faster.
+    pub fn survival(game: &mut Game, config: &DigConfig) -> Dig {
+        Dig::new(game, config, true)
+    }
+
+    fn new(game: &mut Game, config: &DigConfig, survival: bool) -> Dig {
+        game.add_cheese(config.lines);
+        Dig {
+            survival,
+            added: config.lines,
+            due: 0,
+            interval_ms: config.start_ms as f64,
+            timer_ms: config.start_ms as f64,
+            min_ms: config.min_ms as f64,
+            speedup: config.speedup,
+            pieces: game.stats.pieces,
+        }
+    }
+
+    // counts down to the next line, lines rise between pieces so the falling one is never pushed.
+    pub fn tick(&mut self, game: &mut Game, millis: u64) {
+        if !self.survival || game.game_over {
+            return;
+        }
+        self.timer_ms -= millis as f64;
+        while self.timer_ms <= 0.0 {
+            self.due += 1;
+            self.interval_ms = (self.interval_ms * self.speedup).max(self.min_ms);
+            self.timer_ms += self.interval_ms;
+        }
+        if self.due > 0 && game.stats.pieces != self.pieces {
+            game.add_cheese(self.due);
+            self.added += self.due;
+            self.due = 0;
+        }
+        self.pieces = game.stats.pieces;
+    }
+
+    // cheese lines still on the board.
+    pub fn left(&self, game: &Game) -> u32 {
+        game.grid.grid_vec.iter().filter(|row| row.contains(&Cell::Garbage)).count() as u32
+    }
+
+    pub fn dug(&self, game: &Game) -> u32 {
+        self.added - self.left(game)
+    }
+
+    // the race is won, survival only ends by topping out.
+    pub fn cleared(&self, game: &Game) -> bool {
+        !self.survival && self.left(game) == 0
+    }
+
+    // what goes in the bottom border.
+    pub fn status(&self, game: &Game) -> String {
+        match self.survival {
+            true => format!("{} dug", self.dug(game)),
+            false => format!("{} left", self.left(game)),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::io::Stdout;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crossterm::cursor;
+use crossterm::event::{poll, read, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::size as terminal_size;
+
+use crate::fumen;
+use crate::models::cell::{Cell, PieceKind};
+use crate::models::grid::Grid;
+use crate::position::{self, Position};
+use crate::render::{get_text, render_board, render_cursor, render_frame, render_message, Layout, PanelPlacement};
+use crate::terminal::TerminalGuard;
+use crate::theme::Theme;
+
+// fixed keys, shown next to the board.
+const HELP: [(&str, &str); 10] = [
+    ("arrows", "move"),
+    ("space", "paint"),
+    ("del", "erase"),
+    ("iotszjlg", "brush"),
+    ("c", "clear"),
+    ("q", "queue"),
+    ("h", "hold"),
+    ("f", "fumen"),
+    ("enter", "save"),
+    ("esc", "quit"),
+];
+
+// what typed letters go into.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Queue,
+    Hold,
+    Name,
+}
+
+impl Field {
+    fn name(&self) -> &'static str {
+        match self {
+            Field::Queue => "queue",
+            Field::Hold => "hold",
+            Field::Name => "save as",
+        }
+    }
+}
+
+// a position being painted, cell by cell.
+pub struct Editor {
+    pub name: Option<String>,
+    // the board copied out as fumen strings, in order.
+    pub fumens: Vec<String>,
+    grid: Grid,
+    queue: Vec<PieceKind>,
+    hold: Option<PieceKind>,
+    cursor: (usize, usize),
+    brush: Cell,
+}
+
+impl Editor {
+    // an empty board, or `position` when there is one.
+    pub fn new(num_rows: usize, num_cols: usize, name: Option<String>, position: Option<&Position>) -> Result<Editor, String> {
+        let (grid, queue, hold) = match position {
+            Some(position) => (position.grid(num_rows, num_cols)?, position.queue()?, position.hold()?),
+            None => (Grid::new(num_rows, num_cols), vec![], None),
+        };
+        Ok(Editor {
+            name,
+            fumens: vec![],
+            grid,
+            queue,
+            hold,
+            cursor: (num_rows - 1, 0),
+            brush: Cell::Garbage,
+        })
+    }
+
+    pub fn position(&self) -> Position {
+        Position::of(&self.grid, &self.queue, self.hold)
+    }
+
+    fn paint(&mut self, cell: Cell) {
+        let (row, col) = self.cursor;
+        self.grid.grid_vec[row][col] = cell;
+    }
+
+    fn move_cursor(&mut self, rows: isize, cols: isize) {
+        let num_rows = self.grid.grid_vec.len();
+        let num_cols = self.grid.grid_vec[0].len();
+        let (row, col) = self.cursor;
+        self.cursor = (
+            row.saturating_add_signed(rows).min(num_rows - 1),
+            col.saturating_add_signed(cols).min(num_cols - 1),
+        );
+    }
+
+    // takes what was typed into `field`, or says what is wrong with it.
+    fn set(&mut self, field: Field, text: &str) -> Result<Option<PathBuf>, String> {
+        match field {
+            Field::Queue => self.queue = position::pieces("queue", text)?,
+            Field::Hold => {
+                self.hold = match position::pieces("hold", text)?[..] {
+                    [] => None,
+                    [kind] => Some(kind),
+                    _ => return Err("hold: one piece at most".to_string()),
+                }
+            }
+            Field::Name => {
+                let name = text.trim();
+                if name.is_empty() {
+                    return Err("the position needs a name".to_string());
+                }
+                let path = self.position().save(name)?;
+                self.name = Some(name.to_string());
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
+    fn field_text(&self, field: Field) -> String {
+        match field {
+            Field::Queue => self.queue.iter().map(PieceKind::name).collect(),
+            Field::Hold => self.hold.map(|kind| kind.name().to_string()).unwrap_or_default(),
+            Field::Name => self.name.clone().unwrap_or_default(),
+        }
+    }
+}
+
+// blocks until the player quits, returns where the position was last saved.
+pub fn run(stdout: &mut Stdout, terminal: &TerminalGuard, theme: &Theme, editor: &mut Editor) -> Option<PathBuf> {
+    let num_rows = editor.grid.grid_vec.len();
+    let num_cols = editor.grid.grid_vec[0].len();
+    let (term_cols, term_rows) = terminal_size().unwrap();
+    let mut layout = Layout::new(term_cols, term_rows, num_rows, num_cols);
+    render_frame(stdout, theme, &layout);
+    let mut saved = None;
+    // the field being typed into and what was typed so far.
+    let mut typing: Option<(Field, String)> = None;
+    let mut message = String::new();
+
+    loop {
+        render_board(stdout, theme, &layout, &editor.grid.grid_vec);
+        let (row, col) = editor.cursor;
+        render_cursor(stdout, theme, &layout, row, col, editor.brush);
+        render_side(stdout, theme, &layout, editor);
+        if let Some((field, text)) = &typing {
+            // the end of long input, with room for the caret.
+            let width = layout.grid_width as usize - 1;
+            let shown: String = text.chars().skip(text.chars().count().saturating_sub(width)).collect();
+            render_message(stdout, theme, &layout, &[field.name(), "", &format!("{}_", shown), "", "enter / esc"]);
+        } else if !message.is_empty() {
+            render_message(stdout, theme, &layout, &[&message, "", "press any key"]);
+        }
+
+        // wake up now and then so a signal is not stuck behind a blocking read.
+        while !poll(Duration::from_millis(100)).unwrap() {
+            if terminal.quit_requested() {
+                return saved;
+            }
+        }
+        let key_event = match read().unwrap() {
+            Event::Resize(term_cols, term_rows) => {
+                layout = Layout::new(term_cols, term_rows, num_rows, num_cols);
+                render_frame(stdout, theme, &layout);
+                continue;
+            }
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => key_event,
+            _ => continue,
+        };
+        // raw mode swallows the interrupt, so ctrl+c leaves like esc.
+        if key_event.code == KeyCode::Char('c') && key_event.modifiers.contains(KeyModifiers::CONTROL) {
+            return saved;
+        }
+        if !message.is_empty() {
+            message.clear();
+            continue;
+        }
+        if let Some((field, mut text)) = typing.take() {
+            match key_event.code {
+                KeyCode::Enter => match editor.set(field, &text) {
+                    Ok(Some(path)) => {
+                        message = format!("saved {}", editor.name.as_deref().unwrap_or_default());
+                        saved = Some(path);
+                    }
+                    Ok(None) => (),
+                    Err(err) => message = err,
+                },
+                KeyCode::Esc => (),
+                KeyCode::Backspace => {
+                    text.pop();
+                    typing = Some((field, text));
+                }
+                KeyCode::Char(ch) => {
+                    text.push(ch);
+                    typing = Some((field, text));
+                }
+                _ => typing = Some((field, text)),
+            }
+            continue;
+        }
+        match key_event.code {
+            KeyCode::Up => editor.move_cursor(-1, 0),
+            KeyCode::Down => editor.move_cursor(1, 0),
+            KeyCode::Left => editor.move_cursor(0, -1),
+            KeyCode::Right => editor.move_cursor(0, 1),
+            // painting the brush over itself takes it off again.
+            KeyCode::Char(' ') => {
+                let (row, col) = editor.cursor;
+                let cell = match editor.grid.grid_vec[row][col] == editor.brush {
+                    true => Cell::Empty,
+                    false => editor.brush,
+                };
+                editor.paint(cell);
+            }
+            KeyCode::Delete | KeyCode::Backspace => editor.paint(Cell::Empty),
+            KeyCode::Char('c') => editor.grid = Grid::new(num_rows, num_cols),
+            KeyCode::Char('q') => typing = Some((Field::Queue, editor.field_text(Field::Queue))),
+            KeyCode::Char('h') => typing = Some((Field::Hold, editor.field_text(Field::Hold))),
+            KeyCode::Char('f') => match fumen::encode(&editor.position(), num_rows, num_cols) {
+                Ok(fumen) => {
+                    editor.fumens.push(fumen);
+                    message = format!("fumen {}", editor.fumens.len());
+                }
+                Err(err) => message = err,
+            },
+            KeyCode::Enter => typing = Some((Field::Name, editor.field_text(Field::Name))),
+            KeyCode::Esc => return saved,
+            KeyCode::Char(ch) => {
+                if let Some(cell) = Cell::from_letter(ch).filter(|cell| !cell.is_empty()) {
+                    editor.brush = cell;
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// the queue, hold, brush and name where the game has its previews, and the keys where it has its stats.
+fn render_side(stdout: &mut Stdout, theme: &Theme, layout: &Layout, editor: &Editor) {
+    if layout.too_small || layout.panel == PanelPlacement::Hidden {
+        return;
+    }
+    let width = 10;
+    let queue = editor.field_text(Field::Queue);
+    let hold = editor.field_text(Field::Hold);
+    let brush = match editor.brush {
+        Cell::Piece(kind) => kind.name().to_string(),
+        _ => "garbage".to_string(),
+    };
+    let name = editor.field_text(Field::Name);
+    let lines = ["queue", &queue, "", "hold", &hold, "", "brush", &brush, "", "name", &name];
+    for (index, line) in lines.iter().enumerate() {
+        let text: String = format!("{:<width$}", line, width = width).chars().take(width).collect();
+        let color = if index % 3 == 0 { 0 } else { 1 };
+        execute!(stdout, cursor::MoveTo(layout.panel_x + 2, layout.panel_y + index as u16), Print(get_text(theme, &text, &color))).unwrap();
+    }
+    if layout.show_stats {
+        for (index, (key, action)) in HELP.iter().enumerate() {
+            let text = get_text(theme, &format!("{:<10}{:<6}", key, action), &0);
+            execute!(stdout, cursor::MoveTo(layout.stats_x, layout.grid_y + index as u16), Print(text)).unwrap();
+        }
+    }
+}
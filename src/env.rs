@@ -0,0 +1,243 @@
+use crate::bot::Features;
+use crate::config::Ruleset;
+use crate::game::{Game, Input};
+use crate::models::cell::PieceKind;
+use crate::movegen::{self, Placement};
+
+// action indices of the inputs action space.
+pub const INPUTS: [Input; 10] = Input::ALL;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ActionSpace {
+    // one input per step, the index into `INPUTS`.
+    #[default]
+    Inputs,
+    // one piece per step, the index into the observation's placements.
+    Placements,
+}
+
+// what a step is worth, everything is added up.
+#[derive(Clone, Debug)]
+pub struct Rewards {
+    // by the number of lines cleared, 0 to 4.
+    pub lines: [f64; 5],
+    // per line cleared with a t-spin, on top of `lines`.
+    pub t_spin: f64,
+    // per garbage line the clear would send.
+    pub attack: f64,
+    // per hole made, negative to punish them, filling one gives it back.
+    pub holes: f64,
+    pub step: f64,
+    pub game_over: f64,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards {
+            lines: [0.0, 1.0, 3.0, 5.0, 8.0],
+            t_spin: 2.0,
+            attack: 0.0,
+            holes: -0.5,
+            step: 0.01,
+            game_over: -10.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EnvConfig {
+    pub action_space: ActionSpace,
+    pub rewards: Rewards,
+    // gravity time passed after each step of the inputs action space, 0 for none.
+    pub step_ms: u64,
+}
+
+// a reachable placement, offered as an action in the placements action space.
+#[derive(Clone, Debug)]
+pub struct PlacementOption {
+    pub hold: bool,
+    pub kind: PieceKind,
+    // (row, col) from the top left, like the board.
+    pub cells: Vec<(usize, usize)>,
+    // the inputs that place it, ending with the hard drop, without the hold.
+    pub inputs: Vec<Input>,
+    pub t_spin: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Observation {
+    // true for a filled cell, rows from the top.
+    pub board: Vec<Vec<bool>>,
+    pub current: PieceKind,
+    pub queue: Vec<PieceKind>,
+    pub hold: Option<PieceKind>,
+    pub combo: u32,
+    pub back_to_back: bool,
+    // empty in the inputs action space.
+    pub placements: Vec<PlacementOption>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StepInfo {
+    pub lines: u32,
+    pub t_spin: bool,
+    pub attack: u32,
+    pub holes: u32,
+    pub score: i32,
+    pub pieces: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub observation: Observation,
+    pub reward: f64,
+    pub done: bool,
+    pub info: StepInfo,
+}
+
+// a headless game for training agents in process, gym style.
+pub struct Env {
+    config: EnvConfig,
+    ruleset: Ruleset,
+    game: Game,
+    // the placements action space's actions for the current piece, each with its inputs.
+    placements: Vec<(bool, Placement)>,
+    holes: u32,
+}
+
+impl Env {
+    pub fn new(ruleset: &Ruleset, config: EnvConfig) -> Env {
+        let mut env = Env {
+            config,
+            ruleset: ruleset.clone(),
+            game: Game::new(ruleset, ruleset.seed.unwrap_or(0)),
+            placements: vec![],
+            holes: 0,
+        };
+        env.update_placements();
+        env
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = Game::new(&self.ruleset, seed);
+        self.holes = 0;
+        self.update_placements();
+        self.observation()
+    }
+
+    pub fn step(&mut self, action: usize) -> Result<Step, String> {
+        if self.game.game_over {
+            return Err("the game is over, reset to play again".to_string());
+        }
+        let lines = self.game.stats.lines;
+        let attack = self.game.stats.attack;
+        let pieces = self.game.stats.pieces;
+
+        match self.config.action_space {
+            ActionSpace::Inputs => {
+                let input = *INPUTS.get(action).ok_or_else(|| out_of_range(action, INPUTS.len()))?;
+                self.game.input(input);
+                if self.config.step_ms > 0 {
+                    self.game.tick(self.config.step_ms);
+                }
+            }
+            ActionSpace::Placements => {
+                let (hold, placement) = self.placements.get(action).ok_or_else(|| out_of_range(action, self.placements.len()))?;
+                let mut inputs = placement.inputs.clone();
+                if *hold {
+                    inputs.insert(0, Input::Hold);
+                }
+                for input in inputs {
+                    self.game.input(input);
+                }
+            }
+        }
+
+        let holes = Features::of(&self.game.grid).holes;
+        let locked = self.game.stats.pieces != pieces;
+        let info = StepInfo {
+            lines: self.game.stats.lines - lines,
+            t_spin: locked && self.game.last_clear.is_some_and(|clear| clear.t_spin),
+            attack: self.game.stats.attack - attack,
+            holes,
+            score: self.game.score,
+            pieces: self.game.stats.pieces,
+        };
+        let rewards = &self.config.rewards;
+        let mut reward = rewards.step
+            + rewards.lines[info.lines.min(4) as usize]
+            + rewards.attack * info.attack as f64
+            + rewards.holes * (holes as f64 - self.holes as f64);
+        if info.t_spin {
+            reward += rewards.t_spin * info.lines as f64;
+        }
+        if self.game.game_over {
+            reward += rewards.game_over;
+        }
+        self.holes = holes;
+        self.update_placements();
+
+        Ok(Step {
+            observation: self.observation(),
+            reward,
+            done: self.game.game_over,
+            info,
+        })
+    }
+
+    // how many actions the current step accepts.
+    pub fn action_count(&self) -> usize {
+        match self.config.action_space {
+            ActionSpace::Inputs => INPUTS.len(),
+            ActionSpace::Placements => self.placements.len(),
+        }
+    }
+
+    pub fn observation(&self) -> Observation {
+        let game = &self.game;
+        Observation {
+            board: game.grid.grid_vec.iter().map(|row| row.iter().map(|cell| !cell.is_empty()).collect()).collect(),
+            current: game.cur_tetris.kind,
+            queue: vec![game.next_tetris.kind],
+            hold: game.saved_tetris.as_ref().map(|tetris| tetris.kind),
+            combo: game.combo(),
+            back_to_back: game.back_to_back(),
+            placements: self
+                .placements
+                .iter()
+                .map(|(hold, placement)| PlacementOption {
+                    hold: *hold,
+                    kind: placement.tetris.kind,
+                    cells: placement.cells(),
+                    inputs: placement.inputs.clone(),
+                    t_spin: placement.t_spin,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    fn update_placements(&mut self) {
+        self.placements.clear();
+        if self.config.action_space != ActionSpace::Placements || self.game.game_over {
+            return;
+        }
+        let game = &self.game;
+        let held = game.saved_tetris.as_ref().unwrap_or(&game.next_tetris);
+        for placement in movegen::placements(&game.grid, &game.cur_tetris, true) {
+            self.placements.push((false, placement));
+        }
+        if held.kind != game.cur_tetris.kind {
+            for placement in movegen::placements(&game.grid, held, true) {
+                self.placements.push((true, placement));
+            }
+        }
+    }
+}
+
+fn out_of_range(action: usize, count: usize) -> String {
+    format!("action {} is out of range, there are {}", action, count)
+}
@@ -0,0 +1,224 @@
+// the c interface, declared in include/tetri_rs.h. every call returns a status instead of
+// panicking, a panic must not unwind into c.
+use std::panic::{self, UnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::config::{Config, Ruleset};
+use crate::game::{Game, Input};
+use crate::movegen;
+
+// bumped whenever a signature or a #[repr(C)] layout changes.
+pub const TETRI_ABI_VERSION: u32 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TetriStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidArgument = 2,
+    GameOver = 3,
+    BufferTooSmall = 4,
+    Panic = 5,
+}
+
+// opaque to c, only ever handed out boxed.
+pub struct TetriGame {
+    game: Game,
+}
+
+#[repr(C)]
+pub struct TetriState {
+    pub score: i32,
+    pub lines: u32,
+    pub pieces: u32,
+    // piece codes 1 to 7 (I O T S Z J L), `hold` is 0 when nothing is held.
+    pub current: u8,
+    pub next: u8,
+    pub hold: u8,
+    pub game_over: u8,
+}
+
+// the falling piece, (row, col) from the top left.
+#[repr(C)]
+pub struct TetriPiece {
+    pub kind: u8,
+    pub rows: [u32; 4],
+    pub cols: [u32; 4],
+}
+
+fn guard(body: impl FnOnce() -> TetriStatus + UnwindSafe) -> TetriStatus {
+    panic::catch_unwind(body).unwrap_or(TetriStatus::Panic)
+}
+
+#[no_mangle]
+pub extern "C" fn tetri_abi_version() -> u32 {
+    TETRI_ABI_VERSION
+}
+
+/// a new game in `*out`, to be released with `tetri_game_free`.
+///
+/// # Safety
+/// `out` must be null or valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn tetri_game_new(seed: u64, width: u32, height: u32, out: *mut *mut TetriGame) -> TetriStatus {
+    if out.is_null() {
+        return TetriStatus::NullPointer;
+    }
+    guard(|| {
+        let config = Config {
+            ruleset: Ruleset {
+                width: width as usize,
+                height: height as usize,
+                ..Ruleset::default()
+            },
+            ..Config::default()
+        };
+        if config.validate().is_err() {
+            return TetriStatus::InvalidArgument;
+        }
+        let game = Box::new(TetriGame {
+            game: Game::new(&config.ruleset, seed),
+        });
+        unsafe { *out = Box::into_raw(game) };
+        TetriStatus::Ok
+    })
+}
+
+/// # Safety
+/// `game` must be null or from `tetri_game_new`, and not used again.
+#[no_mangle]
+pub unsafe extern "C" fn tetri_game_free(game: *mut TetriGame) {
+    if !game.is_null() {
+        drop(unsafe { Box::from_raw(game) });
+    }
+}
+
+/// `input` is an index into the TETRI_INPUT_* values, in `Input::ALL` order.
+///
+/// # Safety
+/// `game` must be null or a live game from `tetri_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetri_game_input(game: *mut TetriGame, input: u32) -> TetriStatus {
+    let Some(game) = (unsafe { game.as_mut() }) else {
+        return TetriStatus::NullPointer;
+    };
+    let Some(input) = Input::ALL.get(input as usize).copied() else {
+        return TetriStatus::InvalidArgument;
+    };
+    if game.game.game_over {
+        return TetriStatus::GameOver;
+    }
+    guard(panic::AssertUnwindSafe(|| {
+        game.game.input(input);
+        TetriStatus::Ok
+    }))
+}
+
+/// lets `millis` of gravity pass.
+///
+/// # Safety
+/// `game` must be null or a live game from `tetri_game_new`.
+#[no_mangle]
+pub unsafe extern "C" fn tetri_game_tick(game: *mut TetriGame, millis: u64) -> TetriStatus {
+    let Some(game) = (unsafe { game.as_mut() }) else {
+        return TetriStatus::NullPointer;
+    };
+    if game.game.game_over {
+        return TetriStatus::GameOver;
+    }
+    guard(panic::AssertUnwindSafe(|| {
+        game.game.tick(millis);
+        TetriStatus::Ok
+    }))
+}
+
+/// # Safety
+/// `game` must be null or a live game, `width` and `height` null or valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn tetri_game_size(game: *const TetriGame, width: *mut u32, height: *mut u32) -> TetriStatus {
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return TetriStatus::NullPointer;
+    };
+    if width.is_null() || height.is_null() {
+        return TetriStatus::NullPointer;
+    }
+    let grid = &game.game.grid.grid_vec;
+    unsafe {
+        *width = grid.first().map_or(0, |row| row.len()) as u32;
+        *height = grid.len() as u32;
+    }
+    TetriStatus::Ok
+}
+
+/// the locked cells row by row from the top, codes as in `Cell::code`, the falling piece not included.
+///
+/// # Safety
+/// `game` must be null or a live game, `cells` null or valid for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tetri_game_board(game: *const TetriGame, cells: *mut u8, len: usize) -> TetriStatus {
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return TetriStatus::NullPointer;
+    };
+    if cells.is_null() {
+        return TetriStatus::NullPointer;
+    }
+    let grid = &game.game.grid.grid_vec;
+    let needed = grid.len() * grid.first().map_or(0, |row| row.len());
+    if len < needed {
+        return TetriStatus::BufferTooSmall;
+    }
+    let cells = unsafe { slice::from_raw_parts_mut(cells, needed) };
+    for (out, cell) in cells.iter_mut().zip(grid.iter().flatten()) {
+        *out = cell.code();
+    }
+    TetriStatus::Ok
+}
+
+/// # Safety
+/// `game` must be null or a live game, `state` null or valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn tetri_game_state(game: *const TetriGame, state: *mut TetriState) -> TetriStatus {
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return TetriStatus::NullPointer;
+    };
+    if state.is_null() {
+        return TetriStatus::NullPointer;
+    }
+    let game = &game.game;
+    let value = TetriState {
+        score: game.score,
+        lines: game.stats.lines,
+        pieces: game.stats.pieces,
+        current: game.cur_tetris.kind.code(),
+        next: game.next_tetris.kind.code(),
+        hold: game.saved_tetris.as_ref().map_or(0, |tetris| tetris.kind.code()),
+        game_over: game.game_over as u8,
+    };
+    unsafe { ptr::write(state, value) };
+    TetriStatus::Ok
+}
+
+/// # Safety
+/// `game` must be null or a live game, `piece` null or valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn tetri_game_piece(game: *const TetriGame, piece: *mut TetriPiece) -> TetriStatus {
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return TetriStatus::NullPointer;
+    };
+    if piece.is_null() {
+        return TetriStatus::NullPointer;
+    }
+    let tetris = &game.game.cur_tetris;
+    let mut value = TetriPiece {
+        kind: tetris.kind.code(),
+        rows: [0; 4],
+        cols: [0; 4],
+    };
+    for (index, (row, col)) in movegen::cells(tetris).into_iter().take(4).enumerate() {
+        value.rows[index] = row as u32;
+        value.cols[index] = col as u32;
+    }
+    unsafe { ptr::write(piece, value) };
+    TetriStatus::Ok
+}
@@ -0,0 +1,79 @@
+use crate::game::Input;
+use crate::models::cell::PieceKind;
+use crate::models::grid::Grid;
+use crate::models::tetris::Tetris;
+use crate::movegen;
+
+// a placement that took more inputs than it had to.
+#[derive(Clone, Debug)]
+pub struct FinesseFault {
+    pub kind: PieceKind,
+    pub used: Vec<Input>,
+    pub optimal: Vec<Input>,
+}
+
+// the fewest inputs that take `spawn` to where `placed` would land with a hard drop,
+// none if the placement needs a soft drop, like a tuck or a t-spin.
+pub fn optimal_inputs(grid: &Grid, spawn: &Tetris, placed: &Tetris) -> Option<Vec<Input>> {
+    let target = movegen::cells(&placed.get_droped_tetris(&grid.grid_vec));
+    let placement = movegen::placements(grid, spawn, false)
+        .into_iter()
+        .find(|placement| placement.cells() == target)?;
+    let mut inputs = placement.inputs;
+    inputs.pop();
+    Some(inputs)
+}
+
+// compares what the player pressed for a piece with the optimal inputs.
+pub fn check(grid: &Grid, spawn: &Tetris, placed: &Tetris, used: &[Input]) -> Option<FinesseFault> {
+    let used = collapse_shifts(used);
+    let optimal = optimal_inputs(grid, spawn, placed)?;
+    if used.len() <= optimal.len() {
+        return None;
+    }
+    Some(FinesseFault {
+        kind: placed.kind,
+        used,
+        optimal,
+    })
+}
+
+// a held key repeats its shift, so a run of shifts one way counts as a single das.
+fn collapse_shifts(inputs: &[Input]) -> Vec<Input> {
+    let direction = |input: &Input| match input {
+        Input::MoveLeft | Input::DasLeft => Some(Input::DasLeft),
+        Input::MoveRight | Input::DasRight => Some(Input::DasRight),
+        _ => None,
+    };
+    let mut collapsed: Vec<Input> = vec![];
+    for input in inputs {
+        match (direction(input), collapsed.last().and_then(direction)) {
+            (Some(das), Some(prev)) if das == prev => *collapsed.last_mut().unwrap() = das,
+            _ => collapsed.push(*input),
+        }
+    }
+    collapsed
+}
+
+// short form of a key sequence, `<<` and `>>` being das to the wall.
+pub fn notation(inputs: &[Input]) -> String {
+    let symbols: Vec<&str> = inputs
+        .iter()
+        .map(|input| match input {
+            Input::MoveLeft => "<",
+            Input::MoveRight => ">",
+            Input::DasLeft => "<<",
+            Input::DasRight => ">>",
+            Input::RotateCw => "cw",
+            Input::RotateCcw => "ccw",
+            Input::Rotate180 => "180",
+            Input::SoftDrop => "v",
+            Input::HardDrop => "drop",
+            Input::Hold => "hold",
+        })
+        .collect();
+    match symbols.is_empty() {
+        true => "drop".to_string(),
+        false => symbols.join(" "),
+    }
+}
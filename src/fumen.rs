@@ -0,0 +1,471 @@
+use crate::builder::build;
+use crate::game::Game;
+use crate::models::cell::{Cell, PieceKind};
+use crate::models::grid::Grid;
+use crate::models::tetris::Tetris;
+use crate::position::{self, Position};
+
+// fumen boards are 10 wide and 23 high, with one more row below that garbage rises from.
+const WIDTH: usize = 10;
+const HEIGHT: usize = 23;
+const CELLS: usize = WIDTH * (HEIGHT + 1);
+const PREFIX: &str = "v115@";
+const DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+// comments are packed as printable ascii, after javascript's escape().
+const COMMENT_CHARS: u32 = 96;
+const MAX_COMMENT: usize = 4095;
+// the fumen website splits long strings with these.
+const LINE_BREAK: char = '?';
+
+// fumen's own numbers for cells, 0 empty, 8 grey.
+const KINDS: [PieceKind; 7] = [PieceKind::I, PieceKind::L, PieceKind::O, PieceKind::Z, PieceKind::T, PieceKind::J, PieceKind::S];
+const GREY: u8 = 8;
+
+// rotations by fumen's numbers: 0 upside down, 1 right, 2 spawn, 3 left.
+const SPAWN: u32 = 2;
+
+// one page of a fumen, on a board of this game's size.
+pub struct Page {
+    pub grid: Grid,
+    // the piece the page shows, where it shows it.
+    pub piece: Option<Tetris>,
+    pub comment: Option<String>,
+}
+
+impl Page {
+    // the board to practise from, a "#Q=[hold](current)next" comment gives the queue and hold.
+    pub fn position(&self) -> Result<Position, String> {
+        let (queue, hold) = match self.comment.as_deref().and_then(|comment| comment.strip_prefix("#Q=")) {
+            Some(quiz) => parse_quiz(quiz)?,
+            None => (self.piece.iter().map(|piece| piece.kind).collect(), None),
+        };
+        Ok(Position::of(&self.grid, &queue, hold))
+    }
+}
+
+// a page as fumen stores it, cells in reading order from the top left.
+#[derive(Clone)]
+struct RawPage {
+    field: [u8; CELLS],
+    // kind, rotation and position as fumen numbers them.
+    piece: Option<(u8, u32, usize)>,
+    rise: bool,
+    mirror: bool,
+    lock: bool,
+    comment: Option<String>,
+}
+
+// page `number` of a fumen, counted from 1. a link to a fumen site works as well as the bare string.
+pub fn decode(fumen: &str, number: usize, num_rows: usize, num_cols: usize) -> Result<Page, String> {
+    if num_cols != WIDTH {
+        return Err(format!("fumen boards are {} wide, this one is {}", WIDTH, num_cols));
+    }
+    let pages = decode_pages(fumen)?;
+    let raw = match number {
+        0 => None,
+        _ => pages.get(number - 1),
+    }
+    .ok_or_else(|| format!("there is no page {}, the fumen ends at page {}", number, pages.len()))?;
+    page(raw, num_rows)
+}
+
+// every page of a fumen, in order.
+pub fn decode_all(fumen: &str, num_rows: usize, num_cols: usize) -> Result<Vec<Page>, String> {
+    if num_cols != WIDTH {
+        return Err(format!("fumen boards are {} wide, this one is {}", WIDTH, num_cols));
+    }
+    decode_pages(fumen)?
+        .iter()
+        .enumerate()
+        .map(|(index, raw)| page(raw, num_rows).map_err(|err| format!("page {}: {}", index + 1, err)))
+        .collect()
+}
+
+fn page(raw: &RawPage, num_rows: usize) -> Result<Page, String> {
+    if (num_rows..HEIGHT).any(|y| (0..WIDTH).any(|x| raw.field[index(x, y)] != 0)) {
+        return Err(format!("the fumen board is filled higher than the {} rows here", num_rows));
+    }
+    let mut grid = Grid::new(num_rows, WIDTH);
+    for (row, cells) in grid.grid_vec.iter_mut().enumerate() {
+        let y = num_rows - 1 - row;
+        if y >= HEIGHT {
+            continue;
+        }
+        for (x, cell) in cells.iter_mut().enumerate() {
+            *cell = cell_of(raw.field[index(x, y)]);
+        }
+    }
+    let piece = match raw.piece {
+        Some((kind, rotation, position)) => {
+            let cells = piece_cells(kind, rotation, position)
+                .into_iter()
+                .filter(|(x, y)| *y >= 0 && (*y as usize) < num_rows && (0..WIDTH as i32).contains(x))
+                .map(|(x, y)| (num_rows - 1 - y as usize, x as usize))
+                .collect::<Vec<_>>();
+            let kind = KINDS[kind as usize - 1];
+            Some(tetris_at(kind, &cells).ok_or("the fumen piece does not fit on the board")?)
+        }
+        None => None,
+    };
+    Ok(Page {
+        grid,
+        piece,
+        comment: raw.comment.clone(),
+    })
+}
+
+// one page with the board, its queue and hold go into a "#Q=" comment the way fumen quizzes write them.
+pub fn encode(position: &Position, num_rows: usize, num_cols: usize) -> Result<String, String> {
+    if num_cols != WIDTH {
+        return Err(format!("fumen boards are {} wide, this one is {}", WIDTH, num_cols));
+    }
+    let grid = position.grid(num_rows, num_cols)?;
+    let queue = position.queue()?;
+    let hold = position.hold()?;
+    let mut field = [0; CELLS];
+    for (row, cells) in grid.grid_vec.iter().enumerate() {
+        let y = num_rows - 1 - row;
+        for (x, cell) in cells.iter().enumerate() {
+            let code = code_of(*cell);
+            if code == 0 {
+                continue;
+            }
+            if y >= HEIGHT {
+                return Err(format!("fumen boards are {} high, this one is filled higher", HEIGHT));
+            }
+            field[index(x, y)] = code;
+        }
+    }
+    let comment = match (queue.split_first(), hold) {
+        (None, None) => None,
+        (first, hold) => {
+            let name = |kind: Option<&PieceKind>| kind.map(PieceKind::name).unwrap_or_default();
+            let next: String = first.map(|(_, next)| next.iter().map(PieceKind::name).collect()).unwrap_or_default();
+            Some(format!("#Q=[{}]({}){}", name(hold.as_ref()), name(first.map(|(first, _)| first)), next))
+        }
+    };
+    Ok(encode_page(&field, comment.as_deref()))
+}
+
+// the game as it stands: its board, the falling piece and the ones after it, and the hold.
+pub fn encode_game(game: &Game) -> Result<String, String> {
+    let mut queue = vec![game.cur_tetris.kind, game.next_tetris.kind];
+    queue.extend(game.queue.iter().copied());
+    let hold = game.saved_tetris.as_ref().map(|tetris| tetris.kind);
+    let num_rows = game.grid.grid_vec.len();
+    let num_cols = game.grid.grid_vec.first().map_or(0, |row| row.len());
+    encode(&Position::of(&game.grid, &queue, hold), num_rows, num_cols)
+}
+
+fn decode_pages(fumen: &str) -> Result<Vec<RawPage>, String> {
+    let start = fumen.find(PREFIX).ok_or_else(|| format!("not a fumen, expected one starting with {}", PREFIX))?;
+    let mut digits = Digits::new(&fumen[start + PREFIX.len()..])?;
+    let mut pages = vec![];
+    let mut field = [0; CELLS];
+    let mut comment = None;
+    // pages left that repeat the board before them.
+    let mut unchanged = 0;
+
+    while !digits.is_empty() {
+        if unchanged > 0 {
+            unchanged -= 1;
+        } else {
+            // runs of (change + 8) over the previous board.
+            let mut deltas = [0; CELLS];
+            let mut filled = 0;
+            while filled < CELLS {
+                let run = digits.number(2)? as usize;
+                let (delta, count) = (run / CELLS, run % CELLS + 1);
+                if filled + count > CELLS || delta > 16 {
+                    return Err("bad fumen board".to_string());
+                }
+                deltas[filled..filled + count].fill(delta as u8);
+                filled += count;
+            }
+            if deltas.iter().all(|delta| *delta == 8) {
+                unchanged = digits.number(1)?;
+            }
+            for (cell, delta) in field.iter_mut().zip(deltas) {
+                let value = *cell as i32 + delta as i32 - 8;
+                if !(0..=GREY as i32).contains(&value) {
+                    return Err("bad fumen board".to_string());
+                }
+                *cell = value as u8;
+            }
+        }
+
+        let action = digits.number(3)?;
+        let kind = (action % 8) as u8;
+        let rotation = action / 8 % 4;
+        let position = (action / 32 % CELLS as u32) as usize;
+        let flags = action / 32 / CELLS as u32;
+        if kind != 0 && piece_cells(kind, rotation, position).iter().any(|(x, y)| !(0..WIDTH as i32).contains(x) || *y < 0) {
+            return Err("bad fumen piece".to_string());
+        }
+        // a page without a comment of its own keeps the one before.
+        if flags & 0b1000 != 0 {
+            let length = digits.number(2)? as usize;
+            let mut escaped = String::new();
+            while escaped.len() < length {
+                let mut packed = digits.number(5)?;
+                for _ in 0..(length - escaped.len()).min(4) {
+                    escaped.push(char::from(b' ' + (packed % COMMENT_CHARS) as u8));
+                    packed /= COMMENT_CHARS;
+                }
+            }
+            comment = Some(unescape(&escaped));
+        }
+        let page = RawPage {
+            field,
+            piece: (kind != 0).then_some((kind, rotation, position)),
+            rise: flags & 0b1 != 0,
+            mirror: flags & 0b10 != 0,
+            lock: flags & 0b10000 == 0,
+            comment: comment.clone(),
+        };
+        field = next_field(&page);
+        pages.push(page);
+    }
+    if pages.is_empty() {
+        return Err("the fumen has no pages".to_string());
+    }
+    Ok(pages)
+}
+
+// the board the next page starts from: the piece locked, full lines cleared, then rise and mirror.
+fn next_field(page: &RawPage) -> [u8; CELLS] {
+    let mut field = page.field;
+    if let Some((kind, rotation, position)) = page.piece.filter(|_| page.lock) {
+        for (x, y) in piece_cells(kind, rotation, position) {
+            if (y as usize) < HEIGHT {
+                field[index(x as usize, y as usize)] = kind;
+            }
+        }
+    }
+    let mut rows: Vec<[u8; WIDTH]> = (0..HEIGHT)
+        .map(|y| std::array::from_fn(|x| field[index(x, y)]))
+        .filter(|row: &[u8; WIDTH]| !page.lock || row.contains(&0))
+        .collect();
+    let garbage: [u8; WIDTH] = std::array::from_fn(|x| field[HEIGHT * WIDTH + x]);
+    if page.rise {
+        rows.insert(0, garbage);
+    }
+    rows.resize(HEIGHT, [0; WIDTH]);
+    if page.mirror {
+        rows.iter_mut().for_each(|row| row.reverse());
+    }
+    let mut next = [0; CELLS];
+    for (y, row) in rows.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            next[index(x, y)] = *cell;
+        }
+    }
+    if !page.rise {
+        next[HEIGHT * WIDTH..].copy_from_slice(&garbage);
+    }
+    next
+}
+
+fn encode_page(field: &[u8; CELLS], comment: Option<&str>) -> String {
+    let mut digits = String::from(PREFIX);
+    // the change against an empty board, in runs.
+    let mut start = 0;
+    while start < CELLS {
+        let mut end = start + 1;
+        while end < CELLS && field[end] == field[start] {
+            end += 1;
+        }
+        let delta = field[start] as u32 + 8;
+        push_number(&mut digits, delta * CELLS as u32 + (end - start - 1) as u32, 2);
+        start = end;
+    }
+    if field.iter().all(|cell| *cell == 0) {
+        push_number(&mut digits, 0, 1);
+    }
+
+    // no piece, colours on as the fumen site has them, and the comment flag.
+    let mut flags = 0b100;
+    let escaped = comment.map(|comment| {
+        let mut escaped = escape(comment);
+        escaped.truncate(MAX_COMMENT);
+        escaped
+    });
+    if escaped.is_some() {
+        flags |= 0b1000;
+    }
+    push_number(&mut digits, flags * CELLS as u32 * 32, 3);
+    if let Some(escaped) = escaped {
+        push_number(&mut digits, escaped.len() as u32, 2);
+        for chunk in escaped.as_bytes().chunks(4) {
+            let packed = chunk.iter().rev().fold(0, |packed, byte| packed * COMMENT_CHARS + (*byte - b' ') as u32);
+            push_number(&mut digits, packed, 5);
+        }
+    }
+    digits
+}
+
+// fumen numbers, least significant digit first.
+struct Digits {
+    values: Vec<u32>,
+    next: usize,
+}
+
+impl Digits {
+    fn new(text: &str) -> Result<Digits, String> {
+        let values = text
+            .trim()
+            .chars()
+            .filter(|ch| *ch != LINE_BREAK)
+            .map(|ch| {
+                DIGITS
+                    .iter()
+                    .position(|digit| char::from(*digit) == ch)
+                    .map(|value| value as u32)
+                    .ok_or_else(|| format!("'{}' does not belong in a fumen", ch))
+            })
+            .collect::<Result<Vec<u32>, String>>()?;
+        Ok(Digits { values, next: 0 })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.next >= self.values.len()
+    }
+
+    fn number(&mut self, count: usize) -> Result<u32, String> {
+        let digits = self.values.get(self.next..self.next + count).ok_or("the fumen is cut short")?;
+        self.next += count;
+        Ok(digits.iter().rev().fold(0, |number, digit| number * 64 + digit))
+    }
+}
+
+fn push_number(digits: &mut String, mut number: u32, count: usize) {
+    for _ in 0..count {
+        digits.push(char::from(DIGITS[(number % 64) as usize]));
+        number /= 64;
+    }
+}
+
+// the cell at x, and y rows up from the floor.
+fn index(x: usize, y: usize) -> usize {
+    (HEIGHT - 1 - y) * WIDTH + x
+}
+
+fn cell_of(code: u8) -> Cell {
+    match code {
+        0 => Cell::Empty,
+        GREY => Cell::Garbage,
+        code => Cell::Piece(KINDS[code as usize - 1]),
+    }
+}
+
+fn code_of(cell: Cell) -> u8 {
+    match cell {
+        Cell::Empty => 0,
+        Cell::Garbage => GREY,
+        Cell::Piece(kind) => KINDS.iter().position(|known| *known == kind).unwrap() as u8 + 1,
+    }
+}
+
+// (x, y) of a fumen piece, y up from the floor.
+fn piece_cells(kind: u8, rotation: u32, position: usize) -> [(i32, i32); 4] {
+    let (mut x, mut y) = ((position % WIDTH) as i32, (HEIGHT - 1 - position / WIDTH) as i32);
+    let kind = KINDS[kind as usize - 1];
+    // fumen keeps some pieces by another cell than the one they turn around.
+    match (kind, rotation) {
+        (PieceKind::S, 1) => x -= 1,
+        (PieceKind::Z, 3) | (PieceKind::O, 0) | (PieceKind::I, 0) => x += 1,
+        (PieceKind::O, 3) => {
+            x += 1;
+            y -= 1;
+        }
+        (PieceKind::S, 2) | (PieceKind::Z, 2) | (PieceKind::O, 2) | (PieceKind::I, 3) => y -= 1,
+        _ => (),
+    }
+    let shape = match kind {
+        PieceKind::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        PieceKind::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        PieceKind::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+        PieceKind::L => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+        PieceKind::J => [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+        PieceKind::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        PieceKind::Z => [(1, 0), (0, 0), (0, 1), (-1, 1)],
+    };
+    shape.map(|(dx, dy)| {
+        let (dx, dy) = match rotation {
+            SPAWN => (dx, dy),
+            1 => (dy, -dx),
+            0 => (-dx, -dy),
+            _ => (-dy, dx),
+        };
+        (x + dx, y + dy)
+    })
+}
+
+// the spin and shift of `kind` that covers exactly `cells`, given as (row, col).
+fn tetris_at(kind: PieceKind, cells: &[(usize, usize)]) -> Option<Tetris> {
+    let mut wanted: Vec<(usize, usize)> = cells.to_vec();
+    wanted.sort();
+    wanted.dedup();
+    let (top, left) = (wanted.iter().map(|cell| cell.0).min()?, wanted.iter().map(|cell| cell.1).min()?);
+    // far enough from the edges that no spin goes below zero.
+    const MARGIN: usize = 4;
+    for spin in 0..4 {
+        let mut tetris = build::build_tetris(kind, 0, 0);
+        tetris.place(spin, MARGIN, MARGIN);
+        let poses = tetris.get_poses();
+        let (spun_top, spun_left) = (poses.iter().map(|pos| pos.row).min()?, poses.iter().map(|pos| pos.col).min()?);
+        let (Some(row), Some(col)) = ((MARGIN + top).checked_sub(spun_top), (MARGIN + left).checked_sub(spun_left)) else {
+            continue;
+        };
+        tetris.place(spin, row, col);
+        let mut covered: Vec<(usize, usize)> = tetris.get_poses().iter().map(|pos| (pos.row, pos.col)).collect();
+        covered.sort();
+        covered.dedup();
+        if covered == wanted {
+            return Some(tetris);
+        }
+    }
+    None
+}
+
+// "[L](T)SZO": the hold, the current piece and the ones after it, anything after a ';' is left out.
+fn parse_quiz(quiz: &str) -> Result<(Vec<PieceKind>, Option<PieceKind>), String> {
+    let quiz = quiz.split(';').next().unwrap_or_default();
+    let hold = quiz
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .map(|(hold, _)| position::pieces("hold", hold))
+        .transpose()?
+        .and_then(|hold| hold.first().copied());
+    let rest = quiz.split_once(']').map_or(quiz, |(_, rest)| rest);
+    let queue = position::pieces("queue", &rest.replace(['(', ')'], ""))?;
+    Ok((queue, hold))
+}
+
+// javascript's escape(): ascii letters, digits and @*_+-./ as they are, the rest as %XX or %uXXXX.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for unit in text.encode_utf16() {
+        match char::from_u32(unit as u32) {
+            Some(ch) if ch.is_ascii_alphanumeric() || "@*_+-./".contains(ch) => escaped.push(ch),
+            _ if unit < 256 => escaped.push_str(&format!("%{:02X}", unit)),
+            _ => escaped.push_str(&format!("%u{:04X}", unit)),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut units = vec![];
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        let (unit, length) = match (ch, rest.get(1..2), rest.get(2..6), rest.get(1..3)) {
+            ('%', Some("u"), Some(hex), _) if u16::from_str_radix(hex, 16).is_ok() => (u16::from_str_radix(hex, 16).unwrap(), 6),
+            ('%', _, _, Some(hex)) if u8::from_str_radix(hex, 16).is_ok() => (u8::from_str_radix(hex, 16).unwrap() as u16, 3),
+            _ => (ch as u16, ch.len_utf8()),
+        };
+        units.push(unit);
+        rest = &rest[length..];
+    }
+    String::from_utf16_lossy(&units)
+}
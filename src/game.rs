@@ -0,0 +1,367 @@
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::builder::build;
+use crate::config::Ruleset;
+use crate::finesse::{self, FinesseFault};
+use crate::garbage::{self, GarbageQueue, GarbageRules, Holes};
+use crate::models::cell::{Cell, PieceKind};
+use crate::models::grid::Grid;
+use crate::models::tetris::Tetris;
+use crate::stats::{Clear, Stats};
+
+const GARBAGE_SEED: u64 = 0x6761_7262_6167_6521;
+
+// a single player input, everything the game reacts to besides time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+    MoveLeft,
+    MoveRight,
+    // moves all the way to the wall, as holding a direction would.
+    DasLeft,
+    DasRight,
+    SoftDrop,
+    HardDrop,
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    Hold,
+}
+
+impl Input {
+    pub const ALL: [Input; 10] = [
+        Input::MoveLeft,
+        Input::MoveRight,
+        Input::DasLeft,
+        Input::DasRight,
+        Input::SoftDrop,
+        Input::HardDrop,
+        Input::RotateCw,
+        Input::RotateCcw,
+        Input::Rotate180,
+        Input::Hold,
+    ];
+
+    // spelt like the matching key binding actions.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Input::MoveLeft => "move_left",
+            Input::MoveRight => "move_right",
+            Input::DasLeft => "das_left",
+            Input::DasRight => "das_right",
+            Input::SoftDrop => "soft_drop",
+            Input::HardDrop => "hard_drop",
+            Input::RotateCw => "rotate_cw",
+            Input::RotateCcw => "rotate_ccw",
+            Input::Rotate180 => "rotate_180",
+            Input::Hold => "hold",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Input> {
+        Input::ALL.into_iter().find(|input| input.name() == name)
+    }
+}
+
+// one player's board and pieces, with no knowledge of the terminal.
+pub struct Game {
+    pub grid: Grid,
+    pub cur_tetris: Tetris,
+    pub next_tetris: Tetris,
+    pub saved_tetris: Option<Tetris>,
+    pub score: i32,
+    pub stats: Stats,
+    pub last_clear: Option<Clear>,
+    pub last_fault: Option<FinesseFault>,
+    pub game_over: bool,
+    // pieces dealt from the randomizer, the starting two included.
+    pub dealt: u32,
+    // checks each placement against the fewest inputs, off for bots and the
+    // ffi since it runs movegen on every lock.
+    pub finesse: bool,
+    // a finesse fault puts the piece back at the top instead of locking it.
+    pub finesse_restart: bool,
+    // attacks received and waiting to rise.
+    pub garbage: GarbageQueue,
+    // pieces dealt after the next one before the randomizer takes over.
+    pub queue: VecDeque<PieceKind>,
+    // lines sent since `take_sent`, after cancelling.
+    sent: u32,
+    ruleset: Ruleset,
+    rng: StdRng,
+    // separate so receiving garbage does not change the pieces dealt.
+    garbage_rng: StdRng,
+    drop_timer: i64,
+    // -1 until a piece clears lines, then the number of clears in a row minus one.
+    combo: i32,
+    back_to_back: bool,
+    // the current piece as it spawned, and what was pressed since.
+    spawn_tetris: Tetris,
+    piece_inputs: Vec<Input>,
+    soft_dropped: bool,
+}
+
+impl Game {
+    pub fn new(ruleset: &Ruleset, seed: u64) -> Game {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let cur_tetris = build::build_random_tetris(&mut rng, 0, 0);
+        let next_tetris = build::build_random_tetris(&mut rng, 0, 0);
+        Game {
+            grid: Grid::new(ruleset.height, ruleset.width),
+            spawn_tetris: cur_tetris.clone(),
+            cur_tetris,
+            next_tetris,
+            saved_tetris: None,
+            score: 0,
+            stats: Stats::default(),
+            last_clear: None,
+            last_fault: None,
+            game_over: false,
+            dealt: 2,
+            finesse: false,
+            finesse_restart: false,
+            garbage: GarbageQueue::default(),
+            queue: VecDeque::new(),
+            sent: 0,
+            ruleset: ruleset.clone(),
+            rng,
+            garbage_rng: StdRng::seed_from_u64(seed ^ GARBAGE_SEED),
+            drop_timer: ruleset.drop_ms as i64,
+            combo: -1,
+            back_to_back: false,
+            piece_inputs: vec![],
+            soft_dropped: false,
+        }
+    }
+
+    pub fn input(&mut self, input: Input) {
+        if self.game_over {
+            return;
+        }
+        self.stats.record_key();
+        match input {
+            Input::SoftDrop => {
+                self.soft_dropped = true;
+                if !self.cur_tetris.try_move_or_set_tetris(&self.grid.grid_vec, &(1, 0)) {
+                    self.lock();
+                }
+            }
+            Input::HardDrop => {
+                self.cur_tetris.drop_tetris(&self.grid.grid_vec);
+                self.lock();
+            }
+            Input::Hold => self.hold(),
+            _ => {
+                move_tetris(&mut self.cur_tetris, &self.grid, input);
+                self.piece_inputs.push(input);
+            }
+        }
+    }
+
+    // advances gravity, the tetris locks when it cannot fall any further.
+    pub fn tick(&mut self, millis: u64) {
+        if self.game_over {
+            return;
+        }
+        self.stats.elapsed_ms += millis;
+        self.drop_timer -= millis as i64;
+        if self.drop_timer > 0 {
+            return;
+        }
+        self.drop_timer = self.ruleset.drop_ms as i64;
+        if !self.cur_tetris.try_move_or_set_tetris(&self.grid.grid_vec, &(1, 0)) {
+            self.lock();
+        }
+    }
+
+    // clears in a row so far, 0 when the last piece cleared nothing.
+    pub fn combo(&self) -> u32 {
+        (self.combo + 1) as u32
+    }
+
+    pub fn back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+
+    // the locked cells with the falling piece on top, as someone watching sees the board.
+    pub fn board(&self) -> Vec<Vec<Cell>> {
+        let mut board = self.grid.grid_vec.clone();
+        if !self.game_over {
+            for row_col in self.cur_tetris.get_poses() {
+                if let Some(cell) = board.get_mut(row_col.row).and_then(|row| row.get_mut(row_col.col)) {
+                    *cell = Cell::Piece(self.cur_tetris.kind);
+                }
+            }
+        }
+        board
+    }
+
+    // queues an attack, it rises after the next piece that clears nothing.
+    pub fn receive(&mut self, lines: u32) {
+        self.garbage.push(lines);
+    }
+
+    // deals `kinds` first, starting with the current piece, the randomizer carries on after them.
+    pub fn deal_first(&mut self, kinds: &[PieceKind]) {
+        let mut kinds = kinds.iter().copied();
+        if let Some(kind) = kinds.next() {
+            self.cur_tetris = build::build_tetris(kind, 0, 0);
+            self.start_piece();
+        }
+        if let Some(kind) = kinds.next() {
+            self.next_tetris = build::build_tetris(kind, 0, 0);
+        }
+        self.queue = kinds.collect();
+    }
+
+    // raises `lines` of cheese right away, topping out if the stack or the falling piece runs out of room.
+    pub fn add_cheese(&mut self, lines: u32) {
+        let rules = GarbageRules {
+            holes: Holes::Cheese,
+            ..self.ruleset.garbage.clone()
+        };
+        let fits = garbage::raise(&mut self.grid, &[lines], &rules, &mut self.garbage_rng);
+        if !fits || !self.cur_tetris.fits(&self.grid.grid_vec) {
+            self.game_over = true;
+        }
+    }
+
+    // lines to send to the opponent, reset once taken.
+    pub fn take_sent(&mut self) -> u32 {
+        std::mem::take(&mut self.sent)
+    }
+
+    fn hold(&mut self) {
+        let mut cur_tetris = self.cur_tetris.clone();
+        cur_tetris.reset_tetris();
+        match self.saved_tetris.take() {
+            Some(tet) => {
+                self.cur_tetris = tet;
+                self.start_piece();
+            }
+            None => self.spawn_next(),
+        }
+        self.saved_tetris = Some(cur_tetris);
+    }
+
+    fn lock(&mut self) {
+        if (self.finesse || self.finesse_restart) && !self.soft_dropped {
+            if let Some(fault) = finesse::check(&self.grid, &self.spawn_tetris, &self.cur_tetris, &self.piece_inputs) {
+                self.stats.finesse_faults += 1;
+                self.last_fault = Some(fault);
+                if self.finesse_restart {
+                    self.cur_tetris = self.spawn_tetris.clone();
+                    self.start_piece();
+                    self.drop_timer = self.ruleset.drop_ms as i64;
+                    return;
+                }
+            }
+        }
+
+        let t_spin = self.cur_tetris.is_t_spin(&self.grid.grid_vec);
+        let lines_cleared = self.grid.apply_tetris(&self.cur_tetris);
+        self.score += self.get_score(lines_cleared, t_spin);
+
+        let mut clear = Clear {
+            lines: lines_cleared as u32,
+            t_spin,
+            perfect_clear: lines_cleared > 0 && self.grid.grid_vec.iter().flatten().all(|cell| cell.is_empty()),
+            ..Clear::default()
+        };
+        if clear.lines > 0 {
+            self.combo += 1;
+            clear.combo = self.combo as u32;
+            clear.back_to_back = self.back_to_back && clear.is_difficult();
+            self.back_to_back = clear.is_difficult();
+        } else {
+            self.combo = -1;
+        }
+        let attack = self.ruleset.garbage.attack.attack(&clear);
+        self.stats.record_lock(&clear, attack);
+        self.last_clear = Some(clear);
+
+        self.sent += self.garbage.cancel(attack);
+        if clear.lines == 0 {
+            let attacks = self.garbage.take(self.ruleset.garbage.cap);
+            if !garbage::raise(&mut self.grid, &attacks, &self.ruleset.garbage, &mut self.garbage_rng) {
+                self.game_over = true;
+                return;
+            }
+        }
+
+        self.spawn_next();
+    }
+
+    // the next tetris becomes current, topping out if it has no room.
+    fn spawn_next(&mut self) {
+        let next_tetris = match self.queue.pop_front() {
+            Some(kind) => build::build_tetris(kind, 0, 0),
+            None => build::build_random_tetris(&mut self.rng, 0, 0),
+        };
+        self.cur_tetris = std::mem::replace(&mut self.next_tetris, next_tetris);
+        self.dealt += 1;
+        self.start_piece();
+        if !self.cur_tetris.fits(&self.grid.grid_vec) {
+            self.game_over = true;
+        }
+    }
+
+    fn start_piece(&mut self) {
+        self.spawn_tetris = self.cur_tetris.clone();
+        self.piece_inputs.clear();
+        self.soft_dropped = false;
+    }
+
+    fn get_score(&self, lines_cleared: i32, t_spin: bool) -> i32 {
+        let base_multiplier = self.ruleset.base_score;
+        let tetris_multiplier = self.ruleset.tetris_multiplier;
+        let t_spin_multiplier = self.ruleset.t_spin_multiplier;
+
+        let mut score = (lines_cleared * base_multiplier) as f64;
+        if lines_cleared >= 4 {
+            score *= tetris_multiplier;
+        }
+        if t_spin {
+            score *= t_spin_multiplier;
+        }
+
+        score as i32
+    }
+}
+
+// applies a sideways move or a spin, drops and hold are up to the game.
+pub fn move_tetris(tetris: &mut Tetris, grid: &Grid, input: Input) {
+    match input {
+        Input::MoveLeft => {
+            tetris.try_move_or_set_tetris(&grid.grid_vec, &(0, -1));
+        }
+        Input::MoveRight => {
+            tetris.try_move_or_set_tetris(&grid.grid_vec, &(0, 1));
+        }
+        Input::DasLeft => shift_to_wall(tetris, grid, -1),
+        Input::DasRight => shift_to_wall(tetris, grid, 1),
+        Input::RotateCw => {
+            tetris.try_spin_tetris(-1, grid);
+        }
+        Input::RotateCcw => {
+            tetris.try_spin_tetris(1, grid);
+        }
+        Input::Rotate180 => {
+            tetris.try_spin_tetris(2, grid);
+        }
+        Input::SoftDrop | Input::HardDrop | Input::Hold => (),
+    }
+}
+
+// a blocked sideways move still reports success, so stop once the tetris stays put.
+fn shift_to_wall(tetris: &mut Tetris, grid: &Grid, direction: i32) {
+    loop {
+        let before = tetris.get_poses()[0].col;
+        tetris.try_move_or_set_tetris(&grid.grid_vec, &(0, direction));
+        if tetris.get_poses()[0].col == before {
+            break;
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::models::grid::Grid;
+use crate::stats::Clear;
+
+// lines sent for a clear, the defaults are the guideline numbers.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttackTable {
+    // by the number of lines cleared, 0 to 4.
+    pub lines: [u32; 5],
+    pub t_spin: [u32; 4],
+    // by the combo, the last entry for any longer one.
+    pub combo: Vec<u32>,
+    pub back_to_back: u32,
+    pub perfect_clear: u32,
+}
+
+impl Default for AttackTable {
+    fn default() -> Self {
+        AttackTable {
+            lines: [0, 0, 1, 2, 4],
+            t_spin: [0, 2, 4, 6],
+            combo: vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5],
+            back_to_back: 1,
+            perfect_clear: 10,
+        }
+    }
+}
+
+impl AttackTable {
+    pub fn attack(&self, clear: &Clear) -> u32 {
+        if clear.lines == 0 {
+            return 0;
+        }
+        let base = match clear.t_spin {
+            true => self.t_spin[clear.lines.min(3) as usize],
+            false => self.lines[clear.lines.min(4) as usize],
+        };
+        let combo_index = (clear.combo as usize).min(self.combo.len().saturating_sub(1));
+        let combo_bonus = self.combo.get(combo_index).copied().unwrap_or(0);
+        let back_to_back_bonus = if clear.back_to_back { self.back_to_back } else { 0 };
+        let perfect_clear_bonus = if clear.perfect_clear { self.perfect_clear } else { 0 };
+        base + combo_bonus + back_to_back_bonus + perfect_clear_bonus
+    }
+}
+
+// where the holes of incoming garbage go.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Holes {
+    // every line of an attack shares one hole.
+    #[default]
+    Clean,
+    // the hole moves between lines with a chance of `messiness`.
+    Messy,
+    // a new hole every line.
+    Cheese,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GarbageRules {
+    pub attack: AttackTable,
+    pub holes: Holes,
+    pub messiness: f64,
+    // most lines that rise after a single piece, the rest waits for the next one.
+    pub cap: u32,
+}
+
+impl Default for GarbageRules {
+    fn default() -> Self {
+        GarbageRules {
+            attack: AttackTable::default(),
+            holes: Holes::Clean,
+            messiness: 0.3,
+            cap: 8,
+        }
+    }
+}
+
+// incoming attacks waiting to rise, oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct GarbageQueue {
+    pending: Vec<u32>,
+}
+
+impl GarbageQueue {
+    pub fn push(&mut self, lines: u32) {
+        if lines > 0 {
+            self.pending.push(lines);
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.pending.iter().sum()
+    }
+
+    // cancels pending lines with an attack, oldest first, and returns what is left to send.
+    pub fn cancel(&mut self, mut attack: u32) -> u32 {
+        while attack > 0 {
+            let Some(first) = self.pending.first_mut() else {
+                break;
+            };
+            let cancelled = attack.min(*first);
+            *first -= cancelled;
+            attack -= cancelled;
+            if *first == 0 {
+                self.pending.remove(0);
+            }
+        }
+        attack
+    }
+
+    // up to `cap` lines, split into the attacks they came from.
+    pub fn take(&mut self, cap: u32) -> Vec<u32> {
+        let mut taken = vec![];
+        let mut left = cap;
+        while left > 0 {
+            let Some(first) = self.pending.first_mut() else {
+                break;
+            };
+            let lines = left.min(*first);
+            *first -= lines;
+            left -= lines;
+            taken.push(lines);
+            if *first == 0 {
+                self.pending.remove(0);
+            }
+        }
+        taken
+    }
+}
+
+// raises the attacks from the bottom, false if anything was pushed off the top.
+pub fn raise(grid: &mut Grid, attacks: &[u32], rules: &GarbageRules, rng: &mut StdRng) -> bool {
+    let num_cols = grid.grid_vec.first().map_or(0, |row| row.len());
+    if num_cols == 0 {
+        return true;
+    }
+    let mut fits = true;
+    for lines in attacks {
+        let mut hole = rng.gen_range(0..num_cols);
+        for line in 0..*lines {
+            let moves = match rules.holes {
+                Holes::Clean => false,
+                Holes::Messy => line > 0 && rng.gen_bool(rules.messiness.clamp(0.0, 1.0)),
+                Holes::Cheese => line > 0,
+            };
+            if moves {
+                // somewhere else, so the lines never line up by chance.
+                hole = (hole + rng.gen_range(1..num_cols.max(2))) % num_cols;
+            }
+            fits &= grid.insert_garbage(hole);
+        }
+    }
+    fits
+}
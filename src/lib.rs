@@ -0,0 +1,54 @@
+#[macro_use]
+extern crate crossterm;
+
+pub mod models;
+
+pub mod battle;
+
+pub mod builder;
+
+pub mod bindings;
+
+pub mod bot;
+
+pub mod cli;
+
+pub mod config;
+
+pub mod dig;
+
+pub mod editor;
+
+pub mod env;
+
+pub mod ffi;
+
+pub mod finesse;
+
+pub mod fumen;
+
+pub mod game;
+pub mod garbage;
+
+pub mod movegen;
+pub mod net;
+
+pub mod opener;
+pub mod position;
+
+#[cfg(feature = "python")]
+mod python;
+
+pub mod rebind;
+
+pub mod render;
+
+pub mod spectate;
+pub mod stats;
+
+pub mod tbp;
+
+pub mod terminal;
+
+pub mod theme;
+pub mod versus;
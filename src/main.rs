@@ -1,311 +1,446 @@
-extern crate colored;
-use colored::Colorize;
-
 extern crate futures_timer;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-#[macro_use]
-extern crate crossterm;
-use crossterm::cursor;
-use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers, KeyEventKind, KeyEventState};
-use crossterm::style::{Print, Stylize};
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
+use crossterm::event::{poll, read, Event, KeyEventKind};
+use crossterm::terminal::size as terminal_size;
 
+use std::fs;
 use std::io::stdout;
+use std::path::Path;
 
-mod models;
-use crate::models::tetris::Tetris;
-use crate::models::grid::Grid;
-
-mod builder;
-use crate::builder::build;
+use clap::Parser;
+use serde::Serialize;
 
-// TODO: add title screen and score etc.
-// TODO: make the render better.
+use tetri_rs::battle::{self, Battle};
+use tetri_rs::bindings::{Action, Bindings};
+use tetri_rs::bot::{self, Bot};
+use tetri_rs::cli::Cli;
+use tetri_rs::config::{self, Config, Mode};
+use tetri_rs::dig::Dig;
+use tetri_rs::editor::{self, Editor};
+use tetri_rs::fumen;
+use tetri_rs::game::Game;
+use tetri_rs::rebind::run_rebind_screen;
+use tetri_rs::render::{render_frame, render_grid, render_message, render_panel, render_status, render_target_grid, wrap, Layout};
+use tetri_rs::spectate::{self, SpectateServer};
+use tetri_rs::stats::StatsSummary;
+use tetri_rs::terminal::TerminalGuard;
+use tetri_rs::theme::Theme;
+use tetri_rs::net::{self, Peer};
+use tetri_rs::opener::{Hint, Practice};
+use tetri_rs::position::{self, Position};
+use tetri_rs::versus::{self, Outcome, Player};
 
-const CORNER_TOP_LEFT_CHAR: char = '╔';
-const CORNER_TOP_RIGHT_CHAR: char = '╗';
-const CORNER_BOT_LEFT_CHAR: char = '╚';
-const CORNER_BOT_RIGHT_CHAR: char = '╝';
-const BORDER_VERT_CHAR: char = '║';
-const BORDER_HORI_CHAR: char = '═';
+// TODO: add title screen.
 
 fn main() {
+    // load config before touching the terminal so errors stay readable.
+    let cli = Cli::parse();
+    let mut config = Config::load(cli.config.as_deref()).unwrap_or_else(|err| exit_with_error(&err));
+    cli.apply(&mut config);
+    config.validate().unwrap_or_else(|err| exit_with_error(&err));
+    let config_path = cli.config.clone().or_else(config::default_config_path);
+    let theme = Theme::from_config(&config.theme).unwrap_or_else(|err| exit_with_error(&err));
+    let mut bindings = Bindings::from_config(&config.bindings).unwrap_or_else(|err| exit_with_error(&err));
+    let ruleset = &config.ruleset;
+    let num_rows = ruleset.height;
+    let num_cols = ruleset.width;
+
     // init
-    let mut grid = Grid {
-        grid_vec: vec![vec![0; 10]; 20],
+    let seed = ruleset.seed.unwrap_or_else(rand::random);
+    let (start, comment) = match start_position(&cli, &config) {
+        Some((position, comment)) => (Some(position), comment),
+        None => (None, None),
+    };
+    if cli.export_fumen {
+        let position = start.unwrap_or_else(|| exit_with_error("--export-fumen needs --position or --fumen"));
+        println!("{}", fumen::encode(&position, num_rows, num_cols).unwrap_or_else(|err| exit_with_error(&err)));
+        return;
+    }
+    if let Some(name) = &cli.edit {
+        run_editor(name.as_deref(), start, &config, &theme);
+        return;
+    }
+    if let Some(address) = &cli.watch {
+        run_watch(address, &config, &theme, &bindings);
+        return;
+    }
+    if cli.host.is_some() || cli.join.is_some() {
+        run_network(&cli, config, &theme, seed);
+        return;
+    }
+    if config.mode == Mode::Versus {
+        run_versus(&cli, &config, &theme, seed);
+        return;
+    }
+    if config.mode == Mode::Battle {
+        run_battle(&cli, &config, &theme, &bindings, seed);
+        return;
+    }
+    let mut practice = match config.mode {
+        Mode::Opener if start.is_some() => exit_with_error("the opener mode starts from an empty board, leave out --position and --fumen"),
+        Mode::Opener => Some(Practice::new(&config, seed).unwrap_or_else(|err| exit_with_error(&err))),
+        _ => None,
+    };
+    let mut game = match &mut practice {
+        Some(practice) => practice.start(),
+        None => Game::new(ruleset, seed),
     };
+    if let Some(position) = &start {
+        let what = cli.position.as_deref().unwrap_or("fumen");
+        position.start(&mut game).unwrap_or_else(|err| exit_with_error(&format!("{}: {}", what, err)));
+    }
+    game.finesse = config.mode != Mode::Demo;
+    game.finesse_restart = config.handling.finesse_restart;
+    let mut bot = match config.mode {
+        Mode::Demo => Some(Bot::new(&config.bot).unwrap_or_else(|err| exit_with_error(&err))),
+        Mode::Marathon | Mode::Versus | Mode::Battle | Mode::Cheese | Mode::Dig | Mode::Opener => None,
+    };
+    let mut dig = match config.mode {
+        Mode::Cheese => Some(Dig::race(&mut game, &config.dig)),
+        Mode::Dig => Some(Dig::survival(&mut game, &config.dig)),
+        _ => None,
+    };
+    let mut spectate = cli.spectate_port.as_ref().map(|address| {
+        SpectateServer::bind(&net::host_address(address), num_cols, num_rows).unwrap_or_else(|err| exit_with_error(&err))
+    });
+    let mut show_hint = config.handling.hint;
+    let mut hint = None;
+    // the board and pieces the hint was worked out for.
+    let mut hint_for = None;
+    // fumens of the board copied in game, and the piece count when the last one was.
+    let mut fumens = vec![];
+    let mut copied_at = None;
     let mut stdout = stdout();
-    let mut cur_tetris = build::build_random_tetris(0, 0);
-    let mut next_tetris = build::build_random_tetris(0, 0);
-    let mut saved_tetris: Option<Tetris> = None;
 
     // init timers
-    let frame_time_millis = 10;
-    let duration = Duration::from_millis(frame_time_millis as u64);
-    let mut drop_timer = 0;
-    let drop_time_millis = 300;
-
-    // init score
-    let mut score = 0;
+    let frame_time_millis = config.handling.frame_ms;
+    let duration = Duration::from_millis(frame_time_millis);
+    let mut last_tick = Instant::now();
 
     // prep render
-    enable_raw_mode().unwrap();
-    execute!(stdout, Clear(ClearType::All), cursor::MoveTo(0, 0)).unwrap();
-    // render background
-    {
-        for i in 0..=18 {
-            for j in 0..=23 {
-                let cell = get_cell(&3);
-                let row_pos = (2 * i) as u16;
-                execute!(stdout, cursor::MoveTo(row_pos, j), Print(cell)).unwrap();
-            }
-        }
+    let terminal = TerminalGuard::new().unwrap_or_else(|err| exit_with_error(&err.to_string()));
+    let (term_cols, term_rows) = terminal_size().unwrap();
+    let mut layout = Layout::new(term_cols, term_rows, num_rows, num_cols);
+    render_frame(&mut stdout, &theme, &layout);
+    if let Some(comment) = comment.filter(|_| !layout.too_small) {
+        let mut lines = wrap(&comment, layout.grid_width as usize);
+        lines.extend(["".to_string(), "press any key".to_string()]);
+        render_message(&mut stdout, &theme, &layout, &lines.iter().map(String::as_str).collect::<Vec<&str>>());
+        terminal.wait_for_key();
+        render_frame(&mut stdout, &theme, &layout);
+        last_tick = Instant::now();
     }
-    // render frame
-    {
-        let row_start = 0;
-        let row_end = 37;
-        let col_start = 0;
-        let col_end = 23;
 
-        for i in row_start..=row_end {
-            let text = get_text(&BORDER_HORI_CHAR.to_string(), &0);
-            execute!(stdout, cursor::MoveTo(i, col_start), Print(&text)).unwrap();
-            execute!(stdout, cursor::MoveTo(i, col_end), Print(&text)).unwrap();
+    loop {
+        if terminal.quit_requested() {
+            break;
         }
-        for i in col_start..=col_end {
-            let text = get_text(&BORDER_VERT_CHAR.to_string(), &0);
-            execute!(stdout, cursor::MoveTo(row_start, i), Print(&text)).unwrap();
-            execute!(stdout, cursor::MoveTo(row_end, i), Print(&text)).unwrap();
+        render_panel(&mut stdout, &theme, &layout, &game);
+        let hint_key = (game.stats.pieces, game.cur_tetris.kind, game.saved_tetris.as_ref().map(|tet| tet.kind));
+        if show_hint && hint_for != Some(hint_key) {
+            hint = match &practice {
+                Some(practice) => Some(practice.hint(&game)),
+                None => bot::suggest(&game, &config.bot.weights).map(Hint::Place),
+            };
+            hint_for = Some(hint_key);
         }
-
-        let text_corner_top_left = get_text(&CORNER_TOP_LEFT_CHAR.to_string(), &2);
-        let text_corner_top_right = get_text(&CORNER_TOP_RIGHT_CHAR.to_string(), &2);
-        let text_corner_bot_left = get_text(&CORNER_BOT_LEFT_CHAR.to_string(), &2);
-        let text_corner_bot_right = get_text(&CORNER_BOT_RIGHT_CHAR.to_string(), &2);
-
-        execute!(stdout, cursor::MoveTo(row_start, col_start), Print(&text_corner_top_left)).unwrap();
-        execute!(stdout, cursor::MoveTo(row_start, col_end), Print(&text_corner_bot_left)).unwrap();
-        execute!(stdout, cursor::MoveTo(row_end, col_start), Print(&text_corner_top_right)).unwrap();
-        execute!(stdout, cursor::MoveTo(row_end, col_end), Print(&text_corner_bot_right)).unwrap();
-    }
-    // render title
-    {
-        let text_raw = format!("{} T E T I - R S {}", BORDER_VERT_CHAR, BORDER_VERT_CHAR);
-        let text = get_text(&text_raw, &2);
-        execute!(stdout, cursor::MoveTo(10, 0), Print(&text)).unwrap();
-    }
-
-    loop {
-        // render (13, 1) to (15, 2) upcoming and stored.
-        {
-            for i in 12..=17 {
-                for j in 1..=4 {
-                    let cell = get_cell(&0);
-                    let row_pos = (2 * i) as u16;
-                    execute!(stdout, cursor::MoveTo(row_pos, j), Print(cell)).unwrap();
-                }
-            }
-            for row_col in next_tetris.get_tiles() {
-                let new_row = row_col.row + 2;
-                let new_col = row_col.col + 13;
-                execute!(stdout, cursor::MoveTo((new_col * 2) as u16, new_row as u16), Print(get_cell(&next_tetris.color))).unwrap();
-            }
-            for i in 12..=17 {
-                for j in 6..=9 {
-                    let cell = get_cell(&0);
-                    let row_pos = (2 * i) as u16;
-                    execute!(stdout, cursor::MoveTo(row_pos, j), Print(cell)).unwrap();
-                }
-            }
-            if let Some(tet) = &saved_tetris {
-                for row_col in tet.get_tiles() {
-                    let new_row = row_col.row + 7;
-                    let new_col = row_col.col + 13;
-                    execute!(stdout, cursor::MoveTo((new_col * 2) as u16, new_row as u16), Print(get_cell(&tet.color))).unwrap();
-                }
-            }
+        let shown_hint = match &hint {
+            Some(Hint::Place(tetris)) if show_hint => Some(tetris),
+            _ => None,
+        };
+        match &practice {
+            Some(practice) => render_target_grid(&mut stdout, &theme, &layout, &game.grid, &game.cur_tetris, shown_hint, &practice.opener.target),
+            None => render_grid(&mut stdout, &theme, &layout, &game.grid, &game.cur_tetris, shown_hint),
         }
-        // render score
-        {
-
-            for i in 12..=17 {
-                for j in 11..=12 {
-                    let cell = get_cell(&0);
-                    let row_pos = (2 * i) as u16;
-                    execute!(stdout, cursor::MoveTo(row_pos, j), Print(cell)).unwrap();
-                }
-            }
-            let score_preface = get_text(&"score:", &1);
-            let score_string = get_text(&score.to_string(), &1);
-            execute!(stdout, cursor::MoveTo(13 * 2, 11), Print(score_preface)).unwrap();
-            execute!(stdout, cursor::MoveTo(13 * 2, 12), Print(score_string)).unwrap();
-            
+        if let Some(spectate) = &mut spectate {
+            spectate.update(&game);
         }
-        // render (1,1) to (11,21) is tetris grid.
-        {
-            execute!(stdout, cursor::MoveTo(1, 1)).unwrap();
-            let mut rendering_grid_vec = grid.grid_vec.clone();
-            let shadow = cur_tetris.get_droped_tetris(&grid.grid_vec);
-            for row_col in &shadow.get_poses() {
-                *rendering_grid_vec.get_mut(row_col.row).unwrap().get_mut(row_col.col).unwrap() = 7;
-            }
-            for row_col in &cur_tetris.get_poses() {
-                *rendering_grid_vec.get_mut(row_col.row).unwrap().get_mut(row_col.col).unwrap() = cur_tetris.color;
-            }
-            for (index, row) in rendering_grid_vec.iter().enumerate() {
-                let mut row_string_vec: Vec<String> = vec![];
-                for cell in row.iter() {
-                    row_string_vec.push(get_cell(cell));
-                }
-                let row_string = row_string_vec.join("");
-                execute!(stdout, cursor::MoveTo(2, index as u16 + 1), Print(row_string)).unwrap();
+        // a fresh copy shows until the next piece locks, the mode's own status takes over after that.
+        let mode_status = match (&dig, &practice) {
+            (Some(dig), _) => Some(dig.status(&game)),
+            // a hold hint has no cells to draw, so it leads the status instead.
+            (_, Some(practice)) if show_hint && matches!(hint, Some(Hint::Hold)) => Some(format!("hold, {}", practice.status(&game))),
+            (_, Some(practice)) => Some(practice.status(&game)),
+            _ => None,
+        };
+        match (mode_status, copied_at) {
+            (Some(status), at) if at != Some(game.stats.pieces) => render_status(&mut stdout, &theme, &layout, &status),
+            (_, Some(_)) => render_status(&mut stdout, &theme, &layout, &format!("fumen {}", fumens.len())),
+            _ => (),
+        }
+        if let Some(dig) = &dig {
+            if dig.cleared(&game) {
+                let time = format!("{:.1}s", game.stats.elapsed_ms as f64 / 1000.0);
+                render_message(&mut stdout, &theme, &layout, &["C L E A R E D", "", &time, "", "press any key"]);
+                terminal.wait_for_key();
+                break;
             }
         }
-
-        let mut shift = (0_i32, 0_i32);
-        let mut spin = 0;
-        let mut drop = false;
-        let mut save = false;
-        // step drop;
-        drop_timer -= frame_time_millis;
-        if drop_timer <= 0 {
-            shift.0 = 1;
-            drop_timer = drop_time_millis;
+        if game.game_over {
+            render_message(&mut stdout, &theme, &layout, &["G A M E  O V E R", "", "press any key"]);
+            terminal.wait_for_key();
+            break;
         }
+
         // get io and wait;
         if poll(duration).unwrap() {
             match read().unwrap() {
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('h'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => shift.1 = -1,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('l'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => shift.1 = 1,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('j'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => shift.0 += 1,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char(' '),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => drop = true,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('k'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => spin = -1,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('z'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => spin = 1,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('c'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => save = true,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('q'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: KeyEventState::NONE,
-                }) => break,
+                Event::Resize(term_cols, term_rows) => {
+                    layout = Layout::new(term_cols, term_rows, num_rows, num_cols);
+                    render_frame(&mut stdout, &theme, &layout);
+                }
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    // only quitting makes sense while the game is hidden.
+                    let action = match layout.too_small {
+                        true => bindings.action(&key_event).filter(|action| *action == Action::Quit),
+                        false => bindings.action(&key_event),
+                    };
+                    match action {
+                        Some(Action::Rebind) => {
+                            run_rebind_screen(&mut stdout, &terminal, &theme, &layout, &mut bindings, config_path.as_deref());
+                            render_frame(&mut stdout, &theme, &layout);
+                            last_tick = Instant::now();
+                        }
+                        Some(Action::ToggleHint) => show_hint = !show_hint,
+                        Some(Action::Fumen) => match fumen::encode_game(&game) {
+                            Ok(fumen) => {
+                                fumens.push(fumen);
+                                copied_at = Some(game.stats.pieces);
+                            }
+                            Err(err) => {
+                                render_message(&mut stdout, &theme, &layout, &[&err, "", "press any key"]);
+                                terminal.wait_for_key();
+                                last_tick = Instant::now();
+                            }
+                        },
+                        Some(Action::Quit) => break,
+                        Some(action) if bot.is_none() => {
+                            if let Some(input) = action.input() {
+                                game.input(input);
+                            }
+                        }
+                        _ => (),
+                    }
+                }
                 _ => (),
             }
         };
-        if drop {
-            cur_tetris.drop_tetris(&grid.grid_vec);
 
-            let lines_cleared = grid.apply_tetris(&cur_tetris);
-            score += get_score(lines_cleared);
-
-            cur_tetris = next_tetris;
-            next_tetris = build::build_random_tetris(0, 0);
-        }
-        else if spin != 0 {
-            cur_tetris.try_spin_tetris(spin, &grid);
-        }
-        else if save {
-            match saved_tetris {
-                Some(tet) => {
-                    cur_tetris.reset_tetris();
-                    saved_tetris = Some(cur_tetris);
-                    cur_tetris = tet;
-                },
-                None => {
-                    cur_tetris.reset_tetris();
-                    saved_tetris = Some(cur_tetris);
-                    cur_tetris = next_tetris;
-                    next_tetris = build::build_random_tetris(0, 0);
-                },
-            };
+        // step drop, the game waits while there is no room to show it.
+        let elapsed_millis = last_tick.elapsed().as_millis() as u64;
+        last_tick += Duration::from_millis(elapsed_millis);
+        if !layout.too_small {
+            if let Some(bot) = &mut bot {
+                bot.play(&mut game, elapsed_millis);
+            }
+            game.tick(elapsed_millis);
+            if let Some(dig) = &mut dig {
+                dig.tick(&mut game, elapsed_millis);
+            }
+            // straight into the next attempt, built or not.
+            if let Some(practice) = &mut practice {
+                if practice.check(&game).is_some() {
+                    game = practice.start();
+                    game.finesse = true;
+                    game.finesse_restart = config.handling.finesse_restart;
+                    copied_at = None;
+                    hint_for = None;
+                }
+            }
         }
-        else if !cur_tetris.try_move_or_set_tetris(&grid.grid_vec, &shift) {
-            let lines_cleared = grid.apply_tetris(&cur_tetris);
-            score += get_score(lines_cleared);
+    }
 
-            cur_tetris = next_tetris;
-            next_tetris = build::build_random_tetris(0, 0);
+    drop(terminal);
+    if let Some(err) = bot.as_ref().and_then(|bot| bot.error.as_ref()) {
+        eprintln!("tetri-rs: bot: {}", err);
+    }
+    if let Some(dig) = &dig {
+        match dig.cleared(&game) {
+            true => println!("cleared {} cheese lines", dig.dug(&game)),
+            false => println!("dug {} cheese lines, {} left", dig.dug(&game), dig.left(&game)),
         }
     }
+    if let Some(practice) = &practice {
+        println!("{}", practice.summary());
+    }
+    for (index, fumen) in fumens.iter().enumerate() {
+        println!("fumen {}: {}", index + 1, fumen);
+    }
+    let summary = game.stats.summary(game.score);
+    print_summary(&summary);
+    if let Some(path) = &cli.stats_json {
+        write_json(path, &summary);
+    }
+}
 
-    disable_raw_mode().unwrap();
+// the board to start from: a saved position or a fumen page, and the page's comment unless it is a quiz.
+fn start_position(cli: &Cli, config: &Config) -> Option<(Position, Option<String>)> {
+    if let Some(name) = &cli.position {
+        return Some((Position::load(name).unwrap_or_else(|err| exit_with_error(&err)), None));
+    }
+    let fumen = cli.fumen.as_ref()?;
+    let ruleset = &config.ruleset;
+    let page = fumen::decode(fumen, cli.fumen_page, ruleset.height, ruleset.width)
+        .unwrap_or_else(|err| exit_with_error(&format!("fumen: {}", err)));
+    let position = page.position().unwrap_or_else(|err| exit_with_error(&format!("fumen: {}", err)));
+    Some((position, page.comment.filter(|comment| !comment.starts_with("#Q="))))
 }
 
-fn get_cell(cell: &usize) -> String {
-    let cell_uncolored = "██";
-    match cell {
-        // background
-        0 => return format!("{}", Colorize::white(cell_uncolored)),
-        3 => return format!("{}", Colorize::blue(cell_uncolored)),
+fn run_versus(cli: &Cli, config: &Config, theme: &Theme, seed: u64) {
+    let versus_config = &config.versus;
+    let left_bindings = Bindings::from_config(&versus_config.left).unwrap_or_else(|err| exit_with_error(&format!("versus.left: {}", err)));
+    let right_bindings = Bindings::from_config(&versus_config.right).unwrap_or_else(|err| exit_with_error(&format!("versus.right: {}", err)));
+    let [left_seed, right_seed] = versus::seeds(versus_config, seed);
+    let mut players = [
+        Player {
+            game: Game::new(&config.ruleset, left_seed),
+            bindings: left_bindings,
+        },
+        Player {
+            game: Game::new(&config.ruleset, right_seed),
+            bindings: right_bindings,
+        },
+    ];
+    for player in &mut players {
+        player.game.finesse = true;
+    }
+
+    let terminal = TerminalGuard::new().unwrap_or_else(|err| exit_with_error(&err.to_string()));
+    versus::run_local(&mut stdout(), &terminal, theme, config, &mut players);
+    drop(terminal);
 
-        // tetris
-        1 => return format!("{}", Colorize::red(cell_uncolored)),
-        2 => return format!("{}", Colorize::green(cell_uncolored)),
-        4 => return format!("{}", Colorize::purple(cell_uncolored)),
-        5 => return format!("{}", Colorize::cyan(cell_uncolored)),
-        6 => return format!("{}", Colorize::bright_blue(cell_uncolored)),
-        // tetris shadow
-        7 => return format!("{}", Stylize::dark_grey(cell_uncolored)),
-        
-        _ => return format!("{}", Colorize::white(cell_uncolored)),
+    let mut summaries = vec![];
+    for (index, player) in players.iter().enumerate() {
+        let game = &player.game;
+        let won = !game.game_over && players.iter().any(|other| other.game.game_over);
+        println!("player {}{}", index + 1, if won { ", winner" } else { "" });
+        let summary = game.stats.summary(game.score);
+        print_summary(&summary);
+        println!();
+        summaries.push(summary);
+    }
+    if let Some(path) = &cli.stats_json {
+        write_json(path, &summaries);
     }
 }
 
-fn get_text(text: &str, color: &usize) -> String {
-    match color {
-        // background
-        0 => return format!("{}", Colorize::white(text).on_blue()),
-        1 => return format!("{}", Colorize::black(text).on_white()),
-        2 => return format!("{}", Colorize::white(text).on_blue().bold()),
-        
-        _ => return format!("{}", Colorize::white(text)),
+fn run_battle(cli: &Cli, config: &Config, theme: &Theme, bindings: &Bindings, seed: u64) {
+    let mut battle = Battle::new(config, seed).unwrap_or_else(|err| exit_with_error(&err));
+    let terminal = TerminalGuard::new().unwrap_or_else(|err| exit_with_error(&err.to_string()));
+    battle::run(&mut stdout(), &terminal, theme, config, bindings, &mut battle);
+    drop(terminal);
+
+    for opponent in &battle.opponents {
+        if let Some(err) = &opponent.bot.error {
+            eprintln!("tetri-rs: bot: {}", err);
+        }
+    }
+    match battle.finished() {
+        true => println!("place {} of {}, {} kos", battle.place(), battle.opponents.len() + 1, battle.kos()),
+        false => println!("{} of {} opponents left, {} kos", battle.opponents_left(), battle.opponents.len(), battle.kos()),
+    }
+    let summary = battle.player.stats.summary(battle.player.score);
+    print_summary(&summary);
+    if let Some(path) = &cli.stats_json {
+        write_json(path, &summary);
     }
 }
 
-fn get_score(lines_cleared: i32) -> i32 {
-    let base_multiplier = 1000; 
-    let tetris_multiplier = 1.5;
+// paints a position, starting from `start` or else the named one when it was saved before.
+fn run_editor(name: Option<&str>, start: Option<Position>, config: &Config, theme: &Theme) {
+    let position = match (start, name) {
+        (Some(position), _) => Some(position),
+        (None, Some(name)) if position::path(name).is_ok_and(|path| path.exists()) => {
+            Some(Position::load(name).unwrap_or_else(|err| exit_with_error(&err)))
+        }
+        _ => None,
+    };
+    let ruleset = &config.ruleset;
+    let mut editor = Editor::new(ruleset.height, ruleset.width, name.map(str::to_string), position.as_ref())
+        .unwrap_or_else(|err| exit_with_error(&format!("{}: {}", name.unwrap_or_default(), err)));
+    let terminal = TerminalGuard::new().unwrap_or_else(|err| exit_with_error(&err.to_string()));
+    let saved = editor::run(&mut stdout(), &terminal, theme, &mut editor);
+    drop(terminal);
+    for (index, fumen) in editor.fumens.iter().enumerate() {
+        println!("fumen {}: {}", index + 1, fumen);
+    }
+    if let Some(path) = saved {
+        println!("saved {}, play it with --position {}", path.display(), editor.name.unwrap_or_default());
+    }
+}
+
+// someone else's game, read only.
+fn run_watch(address: &str, config: &Config, theme: &Theme, bindings: &Bindings) {
+    let viewer = spectate::connect(address).unwrap_or_else(|err| exit_with_error(&err));
+    let terminal = TerminalGuard::new().unwrap_or_else(|err| exit_with_error(&err.to_string()));
+    let ended = spectate::watch(&mut stdout(), &terminal, theme, bindings, config.handling.frame_ms, &viewer);
+    drop(terminal);
+    if let Some(reason) = ended {
+        println!("{}", reason);
+    }
+}
+
+// one player on each end of a tcp connection, the host decides the board size and seeds.
+fn run_network(cli: &Cli, mut config: Config, theme: &Theme, seed: u64) {
+    let bindings = Bindings::from_config(&config.bindings).unwrap_or_else(|err| exit_with_error(&err));
+    let (mut peer, seed) = match (&cli.host, &cli.join) {
+        (Some(address), _) => {
+            let address = net::host_address(address);
+            println!("waiting for a player on {}", address);
+            let mut peer = Peer::host(&address).unwrap_or_else(|err| exit_with_error(&err));
+            let [_, joiner_seed] = versus::seeds(&config.versus, seed);
+            let ruleset = &config.ruleset;
+            peer.greet(ruleset.width, ruleset.height, joiner_seed).unwrap_or_else(|err| exit_with_error(&err));
+            (peer, seed)
+        }
+        (None, Some(address)) => {
+            let mut peer = Peer::join(address).unwrap_or_else(|err| exit_with_error(&err));
+            let (width, height, seed) = peer.answer().unwrap_or_else(|err| exit_with_error(&err));
+            config.ruleset.width = width;
+            config.ruleset.height = height;
+            config.validate().unwrap_or_else(|err| exit_with_error(&format!("host sent a bad board: {}", err)));
+            (peer, seed)
+        }
+        (None, None) => unreachable!(),
+    };
+    let mut player = Player {
+        game: Game::new(&config.ruleset, seed),
+        bindings,
+    };
+    player.game.finesse = true;
+
+    let terminal = TerminalGuard::new().unwrap_or_else(|err| exit_with_error(&err.to_string()));
+    let outcome = versus::run_online(&mut stdout(), &terminal, theme, &config, &mut player, &mut peer);
+    drop(terminal);
+    drop(peer);
 
-    let mut score = (lines_cleared * base_multiplier) as f64;
-    if lines_cleared >= 4 {
-        score *= tetris_multiplier;
+    match outcome {
+        Outcome::Won => println!("you won"),
+        Outcome::Lost => println!("you lost"),
+        Outcome::Quit => (),
+        Outcome::Disconnected(reason) => println!("disconnected: {}", reason),
+    }
+    let summary = player.game.stats.summary(player.game.score);
+    print_summary(&summary);
+    if let Some(path) = &cli.stats_json {
+        write_json(path, &summary);
     }
-    
-    return score as i32
-}
\ No newline at end of file
+}
+
+fn write_json(path: &Path, value: &impl Serialize) {
+    let json = serde_json::to_string_pretty(value).unwrap();
+    fs::write(path, json + "\n").unwrap_or_else(|err| exit_with_error(&format!("{}: {}", path.display(), err)));
+}
+
+fn print_summary(summary: &StatsSummary) {
+    println!("score      {}", summary.score);
+    for (label, value) in summary.rows() {
+        println!("{:<10} {}", label, value);
+    }
+}
+
+fn exit_with_error(err: &str) -> ! {
+    eprintln!("tetri-rs: {}", err);
+    std::process::exit(1);
+}
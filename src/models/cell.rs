@@ -0,0 +1,94 @@
+// the seven tetrominoes, named by the letter they look like.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PieceKind {
+    I,
+    O,
+    T,
+    S,
+    Z,
+    J,
+    L,
+}
+
+impl PieceKind {
+    pub const ALL: [PieceKind; 7] = [
+        PieceKind::I,
+        PieceKind::O,
+        PieceKind::T,
+        PieceKind::S,
+        PieceKind::Z,
+        PieceKind::J,
+        PieceKind::L,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PieceKind::I => "I",
+            PieceKind::O => "O",
+            PieceKind::T => "T",
+            PieceKind::S => "S",
+            PieceKind::Z => "Z",
+            PieceKind::J => "J",
+            PieceKind::L => "L",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PieceKind> {
+        PieceKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    // 1 to 7 in `ALL` order, for boards handed outside of rust.
+    pub fn code(&self) -> u8 {
+        PieceKind::ALL.iter().position(|kind| kind == self).unwrap() as u8 + 1
+    }
+}
+
+// what a grid square holds, `Piece` remembers which tetris locked it there.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Cell {
+    #[default]
+    Empty,
+    // raised from below by an attack rather than locked by a piece.
+    Garbage,
+    Piece(PieceKind),
+}
+
+impl Cell {
+    pub fn is_empty(&self) -> bool {
+        *self == Cell::Empty
+    }
+
+    // 0 empty, the piece's code, or 8 for garbage.
+    pub fn code(&self) -> u8 {
+        match self {
+            Cell::Empty => 0,
+            Cell::Piece(kind) => kind.code(),
+            Cell::Garbage => 8,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Cell> {
+        match code {
+            0 => Some(Cell::Empty),
+            8 => Some(Cell::Garbage),
+            _ => PieceKind::ALL.into_iter().find(|kind| kind.code() == code).map(Cell::Piece),
+        }
+    }
+
+    // '.' empty, the piece's name, or 'G' for garbage, for files people edit by hand.
+    pub fn letter(&self) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::Piece(kind) => kind.name().chars().next().unwrap(),
+            Cell::Garbage => 'G',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Cell> {
+        match letter.to_ascii_uppercase() {
+            '.' => Some(Cell::Empty),
+            'G' => Some(Cell::Garbage),
+            letter => PieceKind::from_name(&letter.to_string()).map(Cell::Piece),
+        }
+    }
+}
@@ -1,29 +1,45 @@
+use crate::models::cell::Cell;
 use crate::models::tetris::Tetris;
 
 pub struct Grid {
-    pub grid_vec: Vec<Vec<usize>>,
+    pub grid_vec: Vec<Vec<Cell>>,
 }
 
 impl Grid {
+    pub fn new(num_rows: usize, num_cols: usize) -> Self {
+        Grid {
+            grid_vec: vec![vec![Cell::Empty; num_cols]; num_rows],
+        }
+    }
+
     pub fn apply_tetris(&mut self, tetris: &Tetris) -> i32 {
         for row_col in &tetris.get_poses() {
             let row = row_col.row;
             let col = row_col.col;
             *self.grid_vec
                 .get_mut(row).unwrap()
-                .get_mut(col).unwrap() = tetris.color;
+                .get_mut(col).unwrap() = Cell::Piece(tetris.kind);
         }
 
         let mut lines_cleared = 0;
         let cp_grid = self.grid_vec.clone();
         for (index, row) in cp_grid.iter().enumerate() {
-            if !row.contains(&0) {
+            if row.iter().all(|cell| !cell.is_empty()) {
                 lines_cleared += 1;
                 self.grid_vec.remove(index);
-                self.grid_vec.insert(0, vec![0; 10]);
+                self.grid_vec.insert(0, vec![Cell::Empty; row.len()]);
             }
         }
 
-        return lines_cleared
+        lines_cleared
+    }
+
+    // pushes everything up a row and fills the bottom one but for `hole`, false if the top row was not empty.
+    pub fn insert_garbage(&mut self, hole: usize) -> bool {
+        let num_cols = self.grid_vec.first().map_or(0, |row| row.len());
+        let top = self.grid_vec.remove(0);
+        let row = (0..num_cols).map(|col| if col == hole { Cell::Empty } else { Cell::Garbage }).collect();
+        self.grid_vec.push(row);
+        top.iter().all(|cell| cell.is_empty())
     }
 }
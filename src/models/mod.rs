@@ -1,2 +1,3 @@
+pub mod cell;
 pub mod grid;
-pub mod tetris;
\ No newline at end of file
+pub mod tetris;
@@ -1,7 +1,13 @@
-use std::{cmp::{max, min}, usize};
+use std::cmp::{max, min};
 
+use super::cell::{Cell, PieceKind};
 use super::grid::Grid;
 
+// local (row, col) kicks tried in order, after pushing the tetris back inside the grid.
+const SPIN_KICKS: [(i32, i32); 9] = [(0,0), (0,1), (1,1), (1,0), (1,-1), (0,-1), (-1,-1), (-1,0), (-1,1)];
+// a half turn prefers to kick upwards, so flipping a piece in a slot does not sink it.
+const SPIN_180_KICKS: [(i32, i32); 6] = [(0,0), (-1,0), (-1,1), (-1,-1), (0,1), (0,-1)];
+
 #[derive(Clone)]
 pub struct RowCol {
     pub col: usize,
@@ -14,12 +20,13 @@ pub struct Tetris {
     centre: RowCol,
     spin: i32, // 0 - 3, 0 being upright, and 3 being 270 degrees spun.
     shift: RowCol,
-    pub color: usize,
+    rotated: bool, // the last move that changed the tetris was a spin.
+    pub kind: PieceKind,
 }
 
 impl Tetris {
-    pub fn new(tiles: Vec<RowCol>, centre: RowCol, shift: RowCol, spin: i32, color: usize) -> Self {
-        Tetris {tiles, centre, shift, spin, color}
+    pub fn new(tiles: Vec<RowCol>, centre: RowCol, shift: RowCol, spin: i32, kind: PieceKind) -> Self {
+        Tetris {tiles, centre, shift, spin, rotated: false, kind}
     }
 
     // different to get poses, this gets the raw tiles without spin or shift.
@@ -27,6 +34,27 @@ impl Tetris {
         &self.tiles
     }
 
+    pub fn get_spin(&self) -> i32 {
+        self.spin
+    }
+
+    // the (row, col) offset from where it spawned.
+    pub fn get_shift(&self) -> (usize, usize) {
+        (self.shift.row, self.shift.col)
+    }
+
+    // puts the tetris somewhere directly, with no checks against a grid.
+    pub fn place(&mut self, spin: i32, row: usize, col: usize) {
+        self.spin = spin.rem_euclid(4);
+        self.shift = RowCol {row, col};
+        self.rotated = false;
+    }
+
+    // true until the tetris moves after a spin.
+    pub fn get_rotated(&self) -> bool {
+        self.rotated
+    }
+
     // applies the spin and shift.
     pub fn get_poses(&self) -> Vec<RowCol> {
         let mut shifted_poses = vec![];
@@ -46,14 +74,14 @@ impl Tetris {
 
             shifted_poses.push(spun);
         }
-        return shifted_poses
+        shifted_poses
     }
 
-    pub fn try_move_or_set_tetris(&mut self, grid: &Vec<Vec<usize>>, direction: &(i32, i32)) -> bool {
+    pub fn try_move_or_set_tetris(&mut self, grid: &[Vec<Cell>], direction: &(i32, i32)) -> bool {
         if *direction == (0, 0) {
             return true
         }
-        let num_cols = grid.get(0).unwrap().len() as i32;
+        let num_cols = grid.first().unwrap().len() as i32;
         let num_rows = grid.len() as i32;
         for row_col in &self.get_poses() {
             let row = &row_col.row;
@@ -67,16 +95,16 @@ impl Tetris {
             if new_row < 0 || new_row >= num_rows {
                 return false
             }
-            if *grid
+            if !grid
                 .get(new_row as usize).unwrap()
                 .get(*col).unwrap()
-                != 0 as usize {
+                .is_empty() {
                 return false
             }
-            if *grid
+            if !grid
                 .get(new_row as usize).unwrap()
                 .get(new_col as usize).unwrap()
-                != 0 as usize {
+                .is_empty() {
                 return true
             }
         }
@@ -86,23 +114,76 @@ impl Tetris {
             col: (self.shift.col as i32 + direction.1) as usize,
         };
         self.shift = new_shift;
-        return true
+        self.rotated = false;
+        true
+    }
+
+    // every tile inside the grid and on an empty cell.
+    pub fn fits(&self, grid: &[Vec<Cell>]) -> bool {
+        self.get_poses().iter().all(|row_col| {
+            match grid.get(row_col.row).and_then(|grid_row| grid_row.get(row_col.col)) {
+                Some(cell) => cell.is_empty(),
+                None => false,
+            }
+        })
     }
 
-    pub fn drop_tetris(&mut self, grid: &Vec<Vec<usize>>) {
-        while self.try_move_or_set_tetris(grid, &(1, 0)) {}
+    pub fn drop_tetris(&mut self, grid: &[Vec<Cell>]) {
+        let distance = self.drop_distance(grid);
+        self.try_move_or_set_tetris(grid, &(distance, 0));
     }
 
-    pub fn get_droped_tetris(&self, grid: &Vec<Vec<usize>>) -> Tetris {
+    pub fn get_droped_tetris(&self, grid: &[Vec<Cell>]) -> Tetris {
         let mut shadow = self.clone();
-        while shadow.try_move_or_set_tetris(grid, &(1, 0)) {}
-        return shadow
+        shadow.drop_tetris(grid);
+        shadow
+    }
+
+    // rows the tetris can fall before it lands, worked out in one pass rather than a move per row.
+    fn drop_distance(&self, grid: &[Vec<Cell>]) -> i32 {
+        let num_rows = grid.len();
+        self.get_poses()
+            .iter()
+            .map(|row_col| {
+                (row_col.row + 1..num_rows)
+                    .take_while(|row| grid[*row][row_col.col].is_empty())
+                    .count() as i32
+            })
+            .min()
+            .unwrap_or(0)
     }
 
     // reset to 0, 0
     pub fn reset_tetris(&mut self) {
         self.shift = RowCol {row: 0, col: 0};
         self.spin = 0;
+        self.rotated = false;
+    }
+
+    // three corner rule: a t that was spun into place with 3 of the 4 squares
+    // diagonal to its centre filled, the walls and floor count as filled.
+    pub fn is_t_spin(&self, grid: &[Vec<Cell>]) -> bool {
+        if self.kind != PieceKind::T || !self.rotated {
+            return false
+        }
+        let centre_row = self.centre.row as i32 + self.shift.row as i32;
+        let centre_col = self.centre.col as i32 + self.shift.col as i32;
+        let mut filled_corners = 0;
+        for (row_offset, col_offset) in [(-1, -1), (-1, 1), (1, -1), (1, 1)] {
+            let row = centre_row + row_offset;
+            let col = centre_col + col_offset;
+            let filled = match (usize::try_from(row), usize::try_from(col)) {
+                (Ok(row), Ok(col)) => match grid.get(row).and_then(|grid_row| grid_row.get(col)) {
+                    Some(cell) => !cell.is_empty(),
+                    None => true,
+                },
+                _ => true,
+            };
+            if filled {
+                filled_corners += 1;
+            }
+        }
+        filled_corners >= 3
     }
 
     pub fn try_spin_tetris(&mut self, spin: i32, grid: &Grid) -> bool {
@@ -123,42 +204,45 @@ impl Tetris {
         }
         
         // check for out of bounds.
+        let last_row = grid.grid_vec.len() as i32 - 1;
+        let last_col = grid.grid_vec.first().unwrap().len() as i32 - 1;
         for (abs_row, abs_col) in &abs_tiles {
             if *abs_row < 0 {
                 row_shift = max(row_shift, -abs_row);
-            } else if *abs_row > 19 {
-                row_shift = min(row_shift, 19 - abs_row);
+            } else if *abs_row > last_row {
+                row_shift = min(row_shift, last_row - abs_row);
             }
             if *abs_col < 0 {
                 col_shift = max(col_shift, -abs_col);
-            } else if *abs_col > 9 {
-                col_shift = min(col_shift, 9 - abs_col);
+            } else if *abs_col > last_col {
+                col_shift = min(col_shift, last_col - abs_col);
             }
         }
 
         // check for local collision.
         let mut local_shift: Option<(i32, i32)> = None;
-        let try_local_shifts_list = vec![(0,0), (0,1), (1,1), (1,0), (1,-1), (0,-1), (-1,-1), (-1,0), (-1,1)];
-        'outer: for (local_row_shift, local_col_shift) in try_local_shifts_list {
+        let try_local_shifts_list: &[(i32, i32)] = match spin.rem_euclid(4) {
+            2 => &SPIN_180_KICKS,
+            _ => &SPIN_KICKS,
+        };
+        'outer: for &(local_row_shift, local_col_shift) in try_local_shifts_list {
             for (abs_row, abs_col) in &abs_tiles {
                 let adjusted_row = *abs_row + row_shift + local_row_shift;
                 let adjusted_col = *abs_col + col_shift + local_col_shift;
 
-                let row_i: usize;
-                match usize::try_from(adjusted_row) {
-                    Ok(res) => row_i = res,
+                let row_i = match usize::try_from(adjusted_row) {
+                    Ok(res) => res,
                     Err(_res) => continue 'outer,
-                }
-                let col_i: usize;
-                match usize::try_from(adjusted_col) {
-                    Ok(res) => col_i = res,
+                };
+                let col_i = match usize::try_from(adjusted_col) {
+                    Ok(res) => res,
                     Err(_res) => continue 'outer,
-                }
+                };
 
-                if row_i > 19 || col_i > 9 {
+                if row_i as i32 > last_row || col_i as i32 > last_col {
                     continue 'outer
                 }
-                if *grid.grid_vec.get(row_i).unwrap().get(col_i).unwrap() != 0 {
+                if !grid.grid_vec.get(row_i).unwrap().get(col_i).unwrap().is_empty() {
                     continue 'outer
                 }
             }
@@ -166,7 +250,7 @@ impl Tetris {
             break
         }
 
-        if local_shift == None {
+        if local_shift.is_none() {
             return false
         }
 
@@ -177,6 +261,7 @@ impl Tetris {
             col: new_shift_col,
         };
         self.spin = new_spin;
-        return true
+        self.rotated = true;
+        true
     }
 }
\ No newline at end of file
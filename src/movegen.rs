@@ -0,0 +1,96 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::game::{move_tetris, Input};
+use crate::models::cell::PieceKind;
+use crate::models::grid::Grid;
+use crate::models::tetris::Tetris;
+
+// every input the search tries from each position, in order.
+const MOVES: [Input; 8] = [
+    Input::MoveLeft,
+    Input::MoveRight,
+    Input::DasLeft,
+    Input::DasRight,
+    Input::RotateCw,
+    Input::RotateCcw,
+    Input::Rotate180,
+    Input::SoftDrop,
+];
+
+// somewhere the tetris can come to rest, and the shortest way there.
+#[derive(Clone)]
+pub struct Placement {
+    // resting on the stack, ready for `Grid::apply_tetris`.
+    pub tetris: Tetris,
+    // ends with the hard drop.
+    pub inputs: Vec<Input>,
+    pub t_spin: bool,
+}
+
+impl Placement {
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        cells(&self.tetris)
+    }
+}
+
+// every resting position of `tetris` reachable from where it is now, nearest first. soft drops
+// one row at a time open up tucks and spin-ins, without them only hard drops from the current
+// height are found. a spot reached both with and without a t-spin is listed once for each.
+pub fn placements(grid: &Grid, tetris: &Tetris, soft_drop: bool) -> Vec<Placement> {
+    let mut seen = HashSet::new();
+    let mut found = HashSet::new();
+    let mut placements = vec![];
+    let mut queue = VecDeque::new();
+    seen.insert(state(tetris));
+    queue.push_back((tetris.clone(), vec![]));
+
+    while let Some((tetris, inputs)) = queue.pop_front() {
+        let dropped = tetris.get_droped_tetris(&grid.grid_vec);
+        let t_spin = dropped.is_t_spin(&grid.grid_vec);
+        if found.insert((cells(&dropped), t_spin)) {
+            let mut placement_inputs: Vec<Input> = inputs.clone();
+            placement_inputs.push(Input::HardDrop);
+            placements.push(Placement {
+                tetris: dropped,
+                inputs: placement_inputs,
+                t_spin,
+            });
+        }
+
+        for input in MOVES {
+            let mut moved = tetris.clone();
+            match input {
+                Input::SoftDrop if !soft_drop => continue,
+                Input::SoftDrop => {
+                    moved.try_move_or_set_tetris(&grid.grid_vec, &(1, 0));
+                }
+                _ => move_tetris(&mut moved, grid, input),
+            }
+            if seen.insert(state(&moved)) {
+                let mut moved_inputs = inputs.clone();
+                moved_inputs.push(input);
+                queue.push_back((moved, moved_inputs));
+            }
+        }
+    }
+    placements
+}
+
+// the cells a tetris covers, sorted and deduplicated so symmetric orientations compare equal.
+pub fn cells(tetris: &Tetris) -> Vec<(usize, usize)> {
+    let mut cells: Vec<(usize, usize)> = tetris.get_poses().iter().map(|row_col| (row_col.row, row_col.col)).collect();
+    cells.sort();
+    cells.dedup();
+    cells
+}
+
+// the spin decides the next kicks and the rotated flag a t-spin, so both tell positions apart.
+// packed into one number as the search checks it for every move it tries.
+fn state(tetris: &Tetris) -> u64 {
+    let rotated = tetris.kind == PieceKind::T && tetris.get_rotated();
+    let mut key = tetris.get_spin() as u64 * 2 + rotated as u64;
+    for (row, col) in cells(tetris) {
+        key = key * 2048 + (row * 32 + col) as u64;
+    }
+    key
+}
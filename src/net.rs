@@ -0,0 +1,221 @@
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::models::cell::Cell;
+
+// bumped whenever a message changes, both sides have to agree on it.
+pub const PROTOCOL_VERSION: u32 = 1;
+const HANDSHAKE_MS: u64 = 10_000;
+// a peer that stops reading fills the socket, after this long it counts as gone.
+const WRITE_TIMEOUT_MS: u64 = 200;
+
+// one json object per line, both ways.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    // the host sends the board size and the seed the joining player uses, the reply only needs the version.
+    Hello {
+        version: u32,
+        width: usize,
+        height: usize,
+        seed: u64,
+    },
+    // the board with the falling piece on it, rows from the top, one digit per cell as in `Cell::code`.
+    Board {
+        rows: Vec<String>,
+    },
+    Garbage {
+        lines: u32,
+    },
+    GameOver,
+    Ping {
+        millis: u64,
+    },
+    Pong {
+        millis: u64,
+    },
+    Bye,
+}
+
+// the other player at the end of a tcp connection.
+pub struct Peer {
+    stream: TcpStream,
+    messages: Receiver<Result<Message, String>>,
+    // held back until the messages before it are handled.
+    error: Option<String>,
+    started: Instant,
+}
+
+impl Peer {
+    // waits for one player to connect to `address`.
+    pub fn host(address: &str) -> Result<Peer, String> {
+        let listener = TcpListener::bind(address).map_err(|err| format!("{}: {}", address, err))?;
+        let (stream, _) = listener.accept().map_err(|err| format!("{}: {}", address, err))?;
+        Peer::new(stream)
+    }
+
+    pub fn join(address: &str) -> Result<Peer, String> {
+        let addresses = address.to_socket_addrs().map_err(|err| format!("{}: {}", address, err))?;
+        let mut last_err = format!("{}: no address", address);
+        for socket_address in addresses {
+            match TcpStream::connect_timeout(&socket_address, Duration::from_millis(HANDSHAKE_MS)) {
+                Ok(stream) => return Peer::new(stream),
+                Err(err) => last_err = format!("{}: {}", address, err),
+            }
+        }
+        Err(last_err)
+    }
+
+    fn new(stream: TcpStream) -> Result<Peer, String> {
+        // inputs are tiny and late ones are worse than small packets.
+        stream.set_nodelay(true).map_err(|err| err.to_string())?;
+        stream.set_write_timeout(Some(Duration::from_millis(WRITE_TIMEOUT_MS))).map_err(|err| err.to_string())?;
+        let reader = stream.try_clone().map_err(|err| err.to_string())?;
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let message = match line {
+                    Ok(line) => serde_json::from_str(&line).map_err(|err| format!("bad message: {}", err)),
+                    Err(err) => Err(err.to_string()),
+                };
+                let failed = message.is_err();
+                if sender.send(message).is_err() || failed {
+                    return;
+                }
+            }
+            let _ = sender.send(Err("connection closed".to_string()));
+        });
+        Ok(Peer {
+            stream,
+            messages,
+            error: None,
+            started: Instant::now(),
+        })
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), String> {
+        let line = serde_json::to_string(message).unwrap();
+        writeln!(self.stream, "{}", line).map_err(|err| {
+            // a timed out write may have left half a line, nothing after it would parse.
+            let _ = self.stream.shutdown(Shutdown::Write);
+            match err.kind() {
+                ErrorKind::WouldBlock | ErrorKind::TimedOut => format!("connection lost: nothing sent for {}ms", WRITE_TIMEOUT_MS),
+                _ => format!("connection lost: {}", err),
+            }
+        })
+    }
+
+    // the next message within `millis`, for the handshake.
+    pub fn receive(&mut self, millis: u64) -> Result<Message, String> {
+        match self.messages.recv_timeout(Duration::from_millis(millis)) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => Err(format!("no answer within {}ms", millis)),
+            Err(RecvTimeoutError::Disconnected) => Err("connection closed".to_string()),
+        }
+    }
+
+    // everything that arrived since the last call, without waiting.
+    pub fn poll(&mut self) -> Result<Vec<Message>, String> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        let mut messages = vec![];
+        loop {
+            let err = match self.messages.try_recv() {
+                Ok(Ok(message)) => {
+                    messages.push(message);
+                    continue;
+                }
+                Ok(Err(err)) => err,
+                Err(TryRecvError::Empty) => return Ok(messages),
+                Err(TryRecvError::Disconnected) => "connection closed".to_string(),
+            };
+            if messages.is_empty() {
+                return Err(err);
+            }
+            self.error = Some(err);
+            return Ok(messages);
+        }
+    }
+
+    // milliseconds since the connection was made, what pings carry.
+    pub fn clock(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    // sends the host's hello and checks the answer.
+    pub fn greet(&mut self, width: usize, height: usize, seed: u64) -> Result<(), String> {
+        self.send(&Message::Hello {
+            version: PROTOCOL_VERSION,
+            width,
+            height,
+            seed,
+        })?;
+        match self.receive(HANDSHAKE_MS)? {
+            Message::Hello { version, .. } if version == PROTOCOL_VERSION => Ok(()),
+            Message::Hello { version, .. } => Err(version_mismatch(version)),
+            _ => Err("expected a hello".to_string()),
+        }
+    }
+
+    // answers the host's hello, giving its (width, height, seed).
+    pub fn answer(&mut self) -> Result<(usize, usize, u64), String> {
+        let (width, height, seed) = match self.receive(HANDSHAKE_MS)? {
+            Message::Hello { version, width, height, seed } if version == PROTOCOL_VERSION => (width, height, seed),
+            Message::Hello { version, .. } => return Err(version_mismatch(version)),
+            _ => return Err("expected a hello".to_string()),
+        };
+        self.send(&Message::Hello {
+            version: PROTOCOL_VERSION,
+            width,
+            height,
+            seed,
+        })?;
+        Ok((width, height, seed))
+    }
+}
+
+impl Drop for Peer {
+    fn drop(&mut self) {
+        let _ = self.send(&Message::Bye);
+        // wakes the reader thread up.
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+fn version_mismatch(version: u32) -> String {
+    format!("the other side speaks protocol version {}, this is version {}", version, PROTOCOL_VERSION)
+}
+
+// "7777" listens on every interface, anything else is used as is.
+pub fn host_address(address: &str) -> String {
+    match address.parse::<u16>() {
+        Ok(port) => format!("0.0.0.0:{}", port),
+        Err(_) => address.to_string(),
+    }
+}
+
+// the board as a `Board` message sends it.
+pub fn board_rows(game: &Game) -> Vec<String> {
+    game.board()
+        .iter()
+        .map(|row| row.iter().map(|cell| char::from(b'0' + cell.code())).collect())
+        .collect()
+}
+
+// the cells of a `Board` message, anything unreadable shows as empty.
+pub fn parse_rows(rows: &[String]) -> Vec<Vec<Cell>> {
+    rows.iter()
+        .map(|row| {
+            row.bytes()
+                .map(|byte| Cell::from_code(byte.wrapping_sub(b'0')).unwrap_or_default())
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,370 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::builder::build;
+use crate::config::{Config, Ruleset};
+use crate::fumen;
+use crate::game::Game;
+use crate::models::cell::{Cell, PieceKind};
+use crate::models::grid::Grid;
+use crate::models::tetris::Tetris;
+use crate::movegen;
+use crate::position::Position;
+
+const BUILTIN_OPENERS: &str = include_str!("openers.toml");
+const DEFAULT_OPENER_NAME: &str = "tsd";
+// keeps the attempts' pieces apart from the seed's own game.
+const OPENER_SEED: u64 = 0x0be1_e5ee_d000_0001;
+// shuffles tried for an attempt before falling back to the opener's own queue.
+const BAG_TRIES: usize = 50;
+const BAG: [PieceKind; 7] = [
+    PieceKind::I,
+    PieceKind::O,
+    PieceKind::T,
+    PieceKind::S,
+    PieceKind::Z,
+    PieceKind::J,
+    PieceKind::L,
+];
+
+// a board as the searches key it.
+type Cells = Vec<Vec<Cell>>;
+// boards with the queue and hold left that were already searched.
+type Seen = HashSet<(Cells, Vec<PieceKind>, Option<PieceKind>)>;
+
+// how the pieces of an attempt are dealt.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    // the opener's queue as written, every attempt.
+    #[default]
+    Fixed,
+    // shuffled bags of all seven pieces, only ones the opener can be built from with hold.
+    Bag,
+}
+
+// what f3 suggests for the current piece.
+#[derive(Clone)]
+pub enum Hint {
+    Place(Tetris),
+    // the piece has no place on the shape, or only ones the rest can't be built around.
+    Hold,
+}
+
+// an opener as written in toml, a board and queue or a fumen.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenerDef {
+    pub board: Vec<String>,
+    pub queue: String,
+    pub fumen: Option<String>,
+}
+
+// the [opener] table.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenerConfig {
+    pub name: String,
+    pub order: Order,
+    pub openers: HashMap<String, OpenerDef>,
+}
+
+impl Default for OpenerConfig {
+    fn default() -> Self {
+        OpenerConfig {
+            name: DEFAULT_OPENER_NAME.to_string(),
+            order: Order::default(),
+            openers: HashMap::new(),
+        }
+    }
+}
+
+// the shape an opener builds and the queue that builds it.
+pub struct Opener {
+    pub name: String,
+    // the board once built, every cell with the piece meant to fill it.
+    pub target: Grid,
+    queue: Vec<PieceKind>,
+    pub pieces: u32,
+    // where each piece fits from its spawn, by board, the boards an opener passes through are few.
+    spawn_fits: RefCell<HashMap<(Cells, PieceKind), Vec<Tetris>>>,
+}
+
+impl Opener {
+    // the named opener, user ones from [opener.openers] before the built-in ones.
+    pub fn from_config(config: &OpenerConfig, num_rows: usize, num_cols: usize) -> Result<Opener, String> {
+        let mut openers: HashMap<String, OpenerDef> = toml::from_str(BUILTIN_OPENERS).unwrap();
+        for (name, def) in &config.openers {
+            openers.insert(name.clone(), def.clone());
+        }
+        let def = openers.get(&config.name).ok_or_else(|| {
+            let mut names: Vec<&str> = openers.keys().map(String::as_str).collect();
+            names.sort();
+            format!("unknown opener {:?}, expected one of {}", config.name, names.join(", "))
+        })?;
+        Opener::from_def(&config.name, def, num_rows, num_cols).map_err(|err| format!("opener {:?}: {}", config.name, err))
+    }
+
+    fn from_def(name: &str, def: &OpenerDef, num_rows: usize, num_cols: usize) -> Result<Opener, String> {
+        let (target, queue) = match &def.fumen {
+            // the last page with its piece in place, the pieces of all pages in order.
+            Some(fumen) => {
+                let pages = fumen::decode_all(fumen, num_rows, num_cols)?;
+                let last = pages.last().ok_or("the fumen has no pages")?;
+                let mut target = Grid {
+                    grid_vec: last.grid.grid_vec.clone(),
+                };
+                if let Some(piece) = &last.piece {
+                    target.apply_tetris(piece);
+                }
+                (target, pages.iter().filter_map(|page| page.piece.as_ref().map(|piece| piece.kind)).collect())
+            }
+            None => {
+                let position = Position {
+                    board: def.board.clone(),
+                    queue: def.queue.clone(),
+                    hold: None,
+                };
+                (position.grid(num_rows, num_cols)?, position.queue()?)
+            }
+        };
+
+        let cells: Vec<&Cell> = target.grid_vec.iter().flatten().filter(|cell| !cell.is_empty()).collect();
+        if cells.contains(&&Cell::Garbage) {
+            return Err("the board can only hold pieces, not garbage".to_string());
+        }
+        if cells.is_empty() || !cells.len().is_multiple_of(4) {
+            return Err(format!("the board has {} cells filled, not whole pieces", cells.len()));
+        }
+        // openers are built before anything clears, a full row would be gone at once.
+        if target.grid_vec.iter().any(|row| row.iter().all(|cell| !cell.is_empty())) {
+            return Err("the board has a full row".to_string());
+        }
+        let opener = Opener {
+            name: name.to_string(),
+            pieces: cells.len() as u32 / 4,
+            target,
+            queue,
+            spawn_fits: RefCell::new(HashMap::new()),
+        };
+        if !opener.buildable(&Grid::new(num_rows, num_cols), &opener.queue, None) {
+            return Err("the queue cannot build the board, not even with hold".to_string());
+        }
+        Ok(opener)
+    }
+
+    // the cells the shape still wants filled.
+    pub fn left(&self, grid: &Grid) -> usize {
+        self.cells(grid).filter(|(cell, target)| cell.is_empty() && !target.is_empty()).count()
+    }
+
+    // nothing placed anywhere the shape does not have that piece.
+    pub fn on_shape(&self, grid: &Grid) -> bool {
+        self.cells(grid).all(|(cell, target)| cell.is_empty() || cell == target)
+    }
+
+    fn cells<'a>(&'a self, grid: &'a Grid) -> impl Iterator<Item = (&'a Cell, &'a Cell)> {
+        grid.grid_vec.iter().flatten().zip(self.target.grid_vec.iter().flatten())
+    }
+
+    // where `tetris` can go from where it is now, onto cells the shape has for that piece.
+    fn fits(&self, grid: &Grid, tetris: &Tetris) -> Vec<Tetris> {
+        let piece = Cell::Piece(tetris.kind);
+        if !self.cells(grid).any(|(cell, target)| cell.is_empty() && *target == piece) {
+            return vec![];
+        }
+        let mut seen = HashSet::new();
+        movegen::placements(grid, tetris, true)
+            .into_iter()
+            .filter(|placement| {
+                placement.tetris.get_poses().iter().all(|row_col| {
+                    grid.grid_vec[row_col.row][row_col.col].is_empty()
+                        && self.target.grid_vec[row_col.row][row_col.col] == piece
+                })
+            })
+            .filter(|placement| seen.insert(placement.cells()))
+            .map(|placement| placement.tetris)
+            .collect()
+    }
+
+    // whether `queue`, the current piece first, finishes the shape on `grid`, holding pieces as needed.
+    fn buildable(&self, grid: &Grid, queue: &[PieceKind], hold: Option<PieceKind>) -> bool {
+        // with one hold, every piece dealt but the last has to go on the shape.
+        let needed = self.left(grid) / 4 + 1;
+        self.search(grid, queue.iter().take(needed).copied().collect(), hold, &mut HashSet::new())
+    }
+
+    fn search(&self, grid: &Grid, queue: Vec<PieceKind>, hold: Option<PieceKind>, seen: &mut Seen) -> bool {
+        if self.left(grid) == 0 {
+            return true;
+        }
+        let Some((&current, rest)) = queue.split_first() else {
+            return false;
+        };
+        if !seen.insert((grid.grid_vec.clone(), queue.clone(), hold)) {
+            return false;
+        }
+        let key = (grid.grid_vec.clone(), current);
+        let fits = self.spawn_fits.borrow().get(&key).cloned();
+        let fits = fits.unwrap_or_else(|| {
+            let fits = self.fits(grid, &build::build_tetris(current, 0, 0));
+            self.spawn_fits.borrow_mut().insert(key, fits.clone());
+            fits
+        });
+        for tetris in fits {
+            let mut after = Grid {
+                grid_vec: grid.grid_vec.clone(),
+            };
+            after.apply_tetris(&tetris);
+            if self.search(&after, rest.to_vec(), hold, seen) {
+                return true;
+            }
+        }
+        // holding with an empty hold brings on the next piece, otherwise the held one.
+        let swapped = match hold {
+            Some(held) => [&[held], rest].concat(),
+            None => rest.to_vec(),
+        };
+        self.search(grid, swapped, Some(current), seen)
+    }
+}
+
+// attempts at an opener, each a fresh board that starts over at the first piece off the shape.
+pub struct Practice {
+    pub opener: Opener,
+    order: Order,
+    ruleset: Ruleset,
+    rng: StdRng,
+    pub attempts: u32,
+    pub built: u32,
+    // pieces that went on the shape over the finished attempts.
+    placed: u32,
+    // pieces locked in the attempt so far.
+    pieces: u32,
+}
+
+impl Practice {
+    pub fn new(config: &Config, seed: u64) -> Result<Practice, String> {
+        let ruleset = &config.ruleset;
+        let opener = Opener::from_config(&config.opener, ruleset.height, ruleset.width)?;
+        let mut practice = Practice {
+            opener,
+            order: config.opener.order,
+            ruleset: ruleset.clone(),
+            rng: StdRng::seed_from_u64(seed ^ OPENER_SEED),
+            attempts: 0,
+            built: 0,
+            placed: 0,
+            pieces: 0,
+        };
+        if practice.order == Order::Bag && practice.bags().is_none() {
+            return Err(format!("opener {:?} cannot be built from shuffled bags, use the fixed order", practice.opener.name));
+        }
+        Ok(practice)
+    }
+
+    // a fresh attempt, on an empty board with the queue dealt.
+    pub fn start(&mut self) -> Game {
+        let mut game = Game::new(&self.ruleset, self.rng.gen());
+        let queue = match self.order {
+            Order::Fixed => None,
+            Order::Bag => self.bags(),
+        };
+        game.deal_first(queue.as_deref().unwrap_or(&self.opener.queue));
+        self.pieces = 0;
+        game
+    }
+
+    // enough shuffled bags to build the opener from, with a bag to spare for what gets held.
+    fn bags(&mut self) -> Option<Vec<PieceKind>> {
+        let count = self.opener.pieces as usize / BAG.len() + 2;
+        let empty = Grid::new(self.ruleset.height, self.ruleset.width);
+        (0..BAG_TRIES).find_map(|_| {
+            let mut queue = vec![];
+            for _ in 0..count {
+                let mut bag = BAG;
+                bag.shuffle(&mut self.rng);
+                queue.extend(bag);
+            }
+            self.opener.buildable(&empty, &queue, None).then_some(queue)
+        })
+    }
+
+    // after each lock: Some(true) once the shape is built, Some(false) for a piece off it.
+    pub fn check(&mut self, game: &Game) -> Option<bool> {
+        if game.stats.pieces == self.pieces && !game.game_over {
+            return None;
+        }
+        self.pieces = game.stats.pieces;
+        let built = match game.game_over || !self.opener.on_shape(&game.grid) {
+            true => false,
+            false if self.opener.left(&game.grid) == 0 => true,
+            false => return None,
+        };
+        self.attempts += 1;
+        self.built += built as u32;
+        self.placed += self.on_shape(game);
+        Some(built)
+    }
+
+    // pieces of the attempt that went where the opener has them.
+    fn on_shape(&self, game: &Game) -> u32 {
+        let placed = self.opener.cells(&game.grid).filter(|(cell, target)| !cell.is_empty() && cell == target).count();
+        placed as u32 / 4
+    }
+
+    // a placement for the current piece that leaves the rest buildable from the pieces known so far,
+    // or hold when only holding does.
+    pub fn hint(&self, game: &Game) -> Hint {
+        let mut queue = vec![game.next_tetris.kind];
+        queue.extend(game.queue.iter().copied());
+        let hold = game.saved_tetris.as_ref().map(|tetris| tetris.kind);
+        let fits = self.opener.fits(&game.grid, &game.cur_tetris);
+        let buildable = fits.iter().find(|tetris| {
+            let mut after = Grid {
+                grid_vec: game.grid.grid_vec.clone(),
+            };
+            after.apply_tetris(tetris);
+            self.opener.buildable(&after, &queue, hold)
+        });
+        if let Some(tetris) = buildable {
+            return Hint::Place(tetris.clone());
+        }
+        // holding swaps in the held piece, or the next one with an empty hold.
+        let swapped = match hold {
+            Some(held) => [&[held], queue.as_slice()].concat(),
+            None => queue,
+        };
+        match fits.first() {
+            Some(tetris) if !self.opener.buildable(&game.grid, &swapped, Some(game.cur_tetris.kind)) => Hint::Place(tetris.clone()),
+            _ => Hint::Hold,
+        }
+    }
+
+    // what goes in the bottom border.
+    pub fn status(&self, game: &Game) -> String {
+        format!("{}/{} placed, {} of {} built", self.on_shape(game), self.opener.pieces, self.built, self.attempts)
+    }
+
+    // the success rate and how much of the opener the attempts got right, for after the game.
+    pub fn summary(&self) -> String {
+        if self.attempts == 0 {
+            return format!("{}: no attempts finished", self.opener.name);
+        }
+        let percent = |part: u32, whole: u32| 100.0 * part as f64 / whole as f64;
+        format!(
+            "{}: built {} of {} attempts ({:.0}%), {:.0}% of pieces placed right",
+            self.opener.name,
+            self.built,
+            self.attempts,
+            percent(self.built, self.attempts),
+            percent(self.placed, self.attempts * self.opener.pieces)
+        )
+    }
+}
@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::builder::build;
+use crate::config;
+use crate::game::Game;
+use crate::models::cell::{Cell, PieceKind};
+use crate::models::grid::Grid;
+
+const POSITIONS_DIR_NAME: &str = "positions";
+
+// a board with its pieces to practise from, kept as a toml file.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Position {
+    // rows down to the floor, one letter per cell as in `Cell::letter`, empty rows on top can be left out.
+    pub board: Vec<String>,
+    // the current piece first, then the ones after it.
+    pub queue: String,
+    pub hold: Option<String>,
+}
+
+// a bare name lives in $XDG_CONFIG_HOME/tetri-rs/positions, anything with a slash or an extension is a path.
+pub fn path(name: &str) -> Result<PathBuf, String> {
+    if name.contains('/') || name.ends_with(".toml") {
+        return Ok(PathBuf::from(name));
+    }
+    config::config_dir()
+        .map(|dir| dir.join(POSITIONS_DIR_NAME).join(format!("{}.toml", name)))
+        .ok_or_else(|| "no config directory for positions, give a path instead".to_string())
+}
+
+impl Position {
+    pub fn load(name: &str) -> Result<Position, String> {
+        let path = path(name)?;
+        let text = fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn save(&self, name: &str) -> Result<PathBuf, String> {
+        let path = path(name)?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+        }
+        let text = toml::to_string(self).map_err(|err| format!("{}: {}", path.display(), err))?;
+        fs::write(&path, text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        Ok(path)
+    }
+
+    // the board from its highest filled row down.
+    pub fn of(grid: &Grid, queue: &[PieceKind], hold: Option<PieceKind>) -> Position {
+        let rows = &grid.grid_vec;
+        let top = rows.iter().position(|row| row.iter().any(|cell| !cell.is_empty())).unwrap_or(rows.len());
+        Position {
+            board: rows[top..].iter().map(|row| row.iter().map(Cell::letter).collect()).collect(),
+            queue: queue.iter().map(PieceKind::name).collect(),
+            hold: hold.map(|kind| kind.name().to_string()),
+        }
+    }
+
+    // the board on a grid of this size, resting on the floor.
+    pub fn grid(&self, num_rows: usize, num_cols: usize) -> Result<Grid, String> {
+        if self.board.len() > num_rows {
+            return Err(format!("the position is {} rows high, the board only {}", self.board.len(), num_rows));
+        }
+        let mut grid = Grid::new(num_rows, num_cols);
+        let top = num_rows - self.board.len();
+        for (index, row) in self.board.iter().enumerate() {
+            let cells = row
+                .chars()
+                .map(|letter| Cell::from_letter(letter).ok_or_else(|| format!("board: '{}' is not a cell, use IOTSZJL, G or .", letter)))
+                .collect::<Result<Vec<Cell>, String>>()?;
+            if cells.len() != num_cols {
+                return Err(format!("board: row {} is {} cells wide, the board {}", index + 1, cells.len(), num_cols));
+            }
+            grid.grid_vec[top + index] = cells;
+        }
+        Ok(grid)
+    }
+
+    pub fn queue(&self) -> Result<Vec<PieceKind>, String> {
+        pieces("queue", &self.queue)
+    }
+
+    pub fn hold(&self) -> Result<Option<PieceKind>, String> {
+        match &self.hold {
+            Some(hold) => match pieces("hold", hold)?[..] {
+                [kind] => Ok(Some(kind)),
+                _ => Err("hold: one piece at most".to_string()),
+            },
+            None => Ok(None),
+        }
+    }
+
+    // puts the board and pieces into a fresh game, the randomizer deals once the queue runs out.
+    pub fn start(&self, game: &mut Game) -> Result<(), String> {
+        let num_rows = game.grid.grid_vec.len();
+        let num_cols = game.grid.grid_vec.first().map_or(0, |row| row.len());
+        let grid = self.grid(num_rows, num_cols)?;
+        let queue = self.queue()?;
+        let hold = self.hold()?;
+        game.grid = grid;
+        game.deal_first(&queue);
+        game.saved_tetris = hold.map(|kind| build::build_tetris(kind, 0, 0));
+        if !game.cur_tetris.fits(&game.grid.grid_vec) {
+            return Err("the first piece has no room to spawn".to_string());
+        }
+        Ok(())
+    }
+}
+
+// piece letters, case and spaces do not matter.
+pub fn pieces(what: &str, letters: &str) -> Result<Vec<PieceKind>, String> {
+    letters
+        .chars()
+        .filter(|letter| !letter.is_whitespace())
+        .map(|letter| {
+            PieceKind::from_name(&letter.to_ascii_uppercase().to_string())
+                .ok_or_else(|| format!("{}: '{}' is not a piece, use IOTSZJL", what, letter))
+        })
+        .collect()
+}
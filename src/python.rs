@@ -0,0 +1,275 @@
+// the wrappers pyo3 generates around `PyResult` methods trip this lint.
+#![allow(clippy::useless_conversion)]
+
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::config::{Config, Ruleset};
+use crate::env::{ActionSpace, Env, EnvConfig, Observation, Rewards, StepInfo};
+use crate::game::{Game, Input};
+use crate::models::grid::Grid;
+use crate::movegen;
+
+// board values from `Cell::code`: 0 empty, 1 to 7 the pieces I O T S Z J L, 8 garbage.
+fn board_array(grid: &Grid) -> Array2<u8> {
+    let num_rows = grid.grid_vec.len();
+    let num_cols = grid.grid_vec.first().map_or(0, |row| row.len());
+    let values = grid.grid_vec.iter().flatten().map(|cell| cell.code());
+    Array2::from_shape_vec((num_rows, num_cols), values.collect()).unwrap()
+}
+
+// checked like the config file, so python gets the same limits as the game.
+fn ruleset(width: usize, height: usize) -> PyResult<Ruleset> {
+    let config = Config {
+        ruleset: Ruleset {
+            width,
+            height,
+            ..Ruleset::default()
+        },
+        ..Config::default()
+    };
+    config.validate().map_err(PyValueError::new_err)?;
+    Ok(config.ruleset)
+}
+
+#[pyclass(name = "Placement", get_all)]
+#[derive(Clone)]
+struct PyPlacement {
+    hold: bool,
+    kind: &'static str,
+    // (row, col) from the top left.
+    cells: Vec<(usize, usize)>,
+    // input names, ending with the hard drop.
+    inputs: Vec<&'static str>,
+    t_spin: bool,
+}
+
+#[pymethods]
+impl PyPlacement {
+    fn __repr__(&self) -> String {
+        format!("Placement(kind={:?}, hold={}, cells={:?}, t_spin={})", self.kind, self.hold, self.cells, self.t_spin)
+    }
+}
+
+#[pyclass(name = "Game")]
+struct PyGame {
+    game: Game,
+}
+
+#[pymethods]
+impl PyGame {
+    #[new]
+    #[pyo3(signature = (seed=0, width=10, height=20))]
+    fn new(seed: u64, width: usize, height: usize) -> PyResult<PyGame> {
+        Ok(PyGame {
+            game: Game::new(&ruleset(width, height)?, seed),
+        })
+    }
+
+    // one input by name, like "move_left" or "hard_drop".
+    fn input(&mut self, name: &str) -> PyResult<()> {
+        let input = Input::from_name(name).ok_or_else(|| PyValueError::new_err(format!("unknown input {:?}", name)))?;
+        self.game.input(input);
+        Ok(())
+    }
+
+    fn tick(&mut self, millis: u64) {
+        self.game.tick(millis);
+    }
+
+    // presses the inputs of a placement from `placements`.
+    fn play(&mut self, placement: &PyPlacement) -> PyResult<()> {
+        if placement.hold {
+            self.game.input(Input::Hold);
+        }
+        for name in &placement.inputs {
+            self.input(name)?;
+        }
+        Ok(())
+    }
+
+    #[getter]
+    fn board<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<u8>> {
+        board_array(&self.game.grid).into_pyarray_bound(py)
+    }
+
+    #[getter]
+    fn current(&self) -> &'static str {
+        self.game.cur_tetris.kind.name()
+    }
+
+    #[getter]
+    fn next(&self) -> &'static str {
+        self.game.next_tetris.kind.name()
+    }
+
+    #[getter]
+    fn hold(&self) -> Option<&'static str> {
+        self.game.saved_tetris.as_ref().map(|tetris| tetris.kind.name())
+    }
+
+    #[getter]
+    fn score(&self) -> i32 {
+        self.game.score
+    }
+
+    #[getter]
+    fn lines(&self) -> u32 {
+        self.game.stats.lines
+    }
+
+    #[getter]
+    fn pieces(&self) -> u32 {
+        self.game.stats.pieces
+    }
+
+    #[getter]
+    fn game_over(&self) -> bool {
+        self.game.game_over
+    }
+}
+
+// every resting position of the current piece, or of the held one when `hold` is set.
+#[pyfunction]
+#[pyo3(signature = (game, hold=false))]
+fn placements(game: &PyGame, hold: bool) -> Vec<PyPlacement> {
+    let game = &game.game;
+    let tetris = match hold {
+        true => game.saved_tetris.as_ref().unwrap_or(&game.next_tetris),
+        false => &game.cur_tetris,
+    };
+    movegen::placements(&game.grid, tetris, true)
+        .into_iter()
+        .map(|placement| PyPlacement {
+            hold,
+            kind: placement.tetris.kind.name(),
+            cells: placement.cells(),
+            inputs: placement.inputs.iter().map(|input| input.name()).collect(),
+            t_spin: placement.t_spin,
+        })
+        .collect()
+}
+
+#[pyclass(name = "Env")]
+struct PyEnv {
+    env: Env,
+}
+
+#[pymethods]
+impl PyEnv {
+    // `rewards` overrides any of lines (5 values, by lines cleared), t_spin, attack, holes, step and game_over.
+    #[new]
+    #[pyo3(signature = (action_space="inputs", seed=0, width=10, height=20, step_ms=0, rewards=None))]
+    fn new(
+        action_space: &str,
+        seed: u64,
+        width: usize,
+        height: usize,
+        step_ms: u64,
+        rewards: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyEnv> {
+        let action_space = match action_space {
+            "inputs" => ActionSpace::Inputs,
+            "placements" => ActionSpace::Placements,
+            _ => return Err(PyValueError::new_err("action_space must be \"inputs\" or \"placements\"")),
+        };
+        let mut config = EnvConfig {
+            action_space,
+            step_ms,
+            ..EnvConfig::default()
+        };
+        if let Some(rewards) = rewards {
+            set_rewards(&mut config.rewards, rewards)?;
+        }
+        let ruleset = Ruleset {
+            seed: Some(seed),
+            ..ruleset(width, height)?
+        };
+        Ok(PyEnv {
+            env: Env::new(&ruleset, config),
+        })
+    }
+
+    #[pyo3(signature = (seed=None))]
+    fn reset(&mut self, py: Python<'_>, seed: Option<u64>) -> PyResult<PyObject> {
+        let observation = self.env.reset(seed.unwrap_or_else(rand::random));
+        observation_dict(py, &observation)
+    }
+
+    // (observation, reward, done, info) like gym.
+    fn step(&mut self, py: Python<'_>, action: usize) -> PyResult<(PyObject, f64, bool, PyObject)> {
+        let step = self.env.step(action).map_err(PyValueError::new_err)?;
+        Ok((observation_dict(py, &step.observation)?, step.reward, step.done, info_dict(py, &step.info)?))
+    }
+
+    #[getter]
+    fn action_count(&self) -> usize {
+        self.env.action_count()
+    }
+}
+
+fn set_rewards(rewards: &mut Rewards, values: &Bound<'_, PyDict>) -> PyResult<()> {
+    for (key, value) in values.iter() {
+        let key: String = key.extract()?;
+        match key.as_str() {
+            "lines" => rewards.lines = value.extract()?,
+            "t_spin" => rewards.t_spin = value.extract()?,
+            "attack" => rewards.attack = value.extract()?,
+            "holes" => rewards.holes = value.extract()?,
+            "step" => rewards.step = value.extract()?,
+            "game_over" => rewards.game_over = value.extract()?,
+            _ => return Err(PyValueError::new_err(format!("unknown reward {:?}", key))),
+        }
+    }
+    Ok(())
+}
+
+fn observation_dict(py: Python<'_>, observation: &Observation) -> PyResult<PyObject> {
+    let dict = PyDict::new_bound(py);
+    let board: Vec<u8> = observation.board.iter().flatten().map(|filled| *filled as u8).collect();
+    let num_cols = observation.board.first().map_or(0, |row| row.len());
+    let board = Array2::from_shape_vec((observation.board.len(), num_cols), board).unwrap();
+    dict.set_item("board", board.into_pyarray_bound(py))?;
+    dict.set_item("current", observation.current.name())?;
+    dict.set_item("queue", observation.queue.iter().map(|kind| kind.name()).collect::<Vec<_>>())?;
+    dict.set_item("hold", observation.hold.map(|kind| kind.name()))?;
+    dict.set_item("combo", observation.combo)?;
+    dict.set_item("back_to_back", observation.back_to_back)?;
+    let placements: Vec<PyPlacement> = observation
+        .placements
+        .iter()
+        .map(|option| PyPlacement {
+            hold: option.hold,
+            kind: option.kind.name(),
+            cells: option.cells.clone(),
+            inputs: option.inputs.iter().map(|input| input.name()).collect(),
+            t_spin: option.t_spin,
+        })
+        .collect();
+    dict.set_item("placements", placements.into_py(py))?;
+    Ok(dict.into())
+}
+
+fn info_dict(py: Python<'_>, info: &StepInfo) -> PyResult<PyObject> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("lines", info.lines)?;
+    dict.set_item("t_spin", info.t_spin)?;
+    dict.set_item("attack", info.attack)?;
+    dict.set_item("holes", info.holes)?;
+    dict.set_item("score", info.score)?;
+    dict.set_item("pieces", info.pieces)?;
+    Ok(dict.into())
+}
+
+#[pymodule]
+#[pyo3(name = "tetri_rs")]
+fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGame>()?;
+    module.add_class::<PyPlacement>()?;
+    module.add_class::<PyEnv>()?;
+    module.add_function(wrap_pyfunction!(placements, module)?)?;
+    Ok(())
+}
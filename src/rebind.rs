@@ -0,0 +1,124 @@
+use std::io::Stdout;
+use std::path::Path;
+use std::time::Duration;
+
+use crossterm::cursor;
+use crossterm::event::{poll, read, Event, KeyCode, KeyEventKind};
+use crossterm::style::Print;
+
+use crate::bindings::{Action, Bindings, KeyBinding, Preset};
+use crate::config;
+use crate::render::{wrap, Layout};
+use crate::terminal::TerminalGuard;
+use crate::theme::Theme;
+
+// blocks until the player leaves the screen, the keys here are fixed so a
+// broken binding can always be fixed.
+pub fn run_rebind_screen(stdout: &mut Stdout, terminal: &TerminalGuard, theme: &Theme, layout: &Layout, bindings: &mut Bindings, config_path: Option<&Path>) {
+    let mut selected = 0;
+    let mut capturing = false;
+    let mut message = String::new();
+
+    loop {
+        render(stdout, theme, layout, bindings, selected, capturing, &message);
+
+        // wake up now and then so a signal is not stuck behind a blocking read.
+        while !poll(Duration::from_millis(100)).unwrap() {
+            if terminal.quit_requested() {
+                return;
+            }
+        }
+        let key_event = match read().unwrap() {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => key_event,
+            _ => continue,
+        };
+        if capturing {
+            // ctrl+c always quits, so it cancels like esc rather than being bound.
+            let key = KeyBinding::from_event(&key_event);
+            if key_event.code != KeyCode::Esc && !key.is_interrupt() {
+                bindings.set_keys(Action::ALL[selected], vec![key]);
+            }
+            capturing = false;
+            continue;
+        }
+        if !message.is_empty() {
+            return;
+        }
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => selected = (selected + Action::ALL.len() - 1) % Action::ALL.len(),
+            KeyCode::Down | KeyCode::Char('j') => selected = (selected + 1) % Action::ALL.len(),
+            KeyCode::Enter => capturing = true,
+            KeyCode::Char(ch @ '1'..='9') if ch as usize - ('1' as usize) < Preset::ALL.len() => {
+                let preset = Preset::ALL[ch as usize - '1' as usize];
+                *bindings = Bindings::from_preset(preset);
+            }
+            KeyCode::Esc => {
+                let saved = match config_path {
+                    Some(path) => config::save_bindings(path, &bindings.to_config()),
+                    None => Err("no config dir, set $XDG_CONFIG_HOME or $HOME".to_string()),
+                };
+                match saved {
+                    Ok(()) => return,
+                    Err(err) => message = format!("not saved: {}", err),
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn render(stdout: &mut Stdout, theme: &Theme, layout: &Layout, bindings: &Bindings, selected: usize, capturing: bool, message: &str) {
+    let mut help = vec!["up/down select, enter rebind".to_string()];
+    help.extend(preset_keys());
+    help.extend([
+        "esc save and exit".to_string(),
+        format!("preset: {}", bindings.preset.name()),
+    ]);
+    // the prompt and the error take the place of the help, so the list stays put.
+    let width = layout.frame_width.saturating_sub(4) as usize;
+    let shown = if capturing {
+        vec![format!("press a key for {}", Action::ALL[selected].name()), "esc to cancel".to_string()]
+    } else if !message.is_empty() {
+        let mut shown = wrap(message, width.saturating_sub(2));
+        shown.truncate(help.len());
+        shown.push("press any key".to_string());
+        shown
+    } else {
+        help.clone()
+    };
+    let mut lines = vec!["R E B I N D".to_string(), String::new()];
+    lines.extend(shown.into_iter().chain(std::iter::repeat(String::new())).take(help.len() + 1));
+
+    // scrolls the actions when the frame is too short for all of them.
+    let num_rows = layout.frame_height.saturating_sub(2) as usize;
+    let room = num_rows.saturating_sub(lines.len()).max(1);
+    let first_action = selected.saturating_sub(room - 1);
+    let first_action_line = lines.len();
+    for action in Action::ALL.iter().skip(first_action).take(room) {
+        let keys: Vec<String> = bindings.keys_for(*action).iter().map(|key| key.to_string()).collect();
+        lines.push(format!("{:<12}{}", action.name(), keys.join(", ")));
+    }
+
+    // fills the inside of the frame.
+    for row in 0..num_rows as u16 {
+        let line = lines.get(row as usize).cloned().unwrap_or_default();
+        let is_selected = row as usize == first_action_line + selected - first_action;
+        let prefix = if is_selected { "> " } else { "  " };
+        let text: String = format!("{}{:<width$}", prefix, line, width = width).chars().take(width).collect();
+        let painted = match is_selected {
+            true => theme.paint(&text, theme.text, Some(theme.text_bg)),
+            false => theme.paint(&text, theme.frame, Some(theme.frame_bg)),
+        };
+        execute!(stdout, cursor::MoveTo(layout.grid_x, layout.grid_y + row), Print(painted)).unwrap();
+    }
+}
+
+// "1 vim, 2 arrows, 3 wasd", three to a line so the frame fits them.
+fn preset_keys() -> Vec<String> {
+    let names: Vec<String> = Preset::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, preset)| format!("{} {}", index + 1, preset.name()))
+        .collect();
+    names.chunks(3).map(|chunk| chunk.join(", ")).collect()
+}
@@ -0,0 +1,499 @@
+use std::io::Stdout;
+
+use crossterm::cursor;
+use crossterm::style::Print;
+use crossterm::terminal::{Clear, ClearType};
+
+use crate::finesse;
+use crate::game::Game;
+use crate::models::cell::Cell;
+use crate::models::grid::Grid;
+use crate::models::tetris::Tetris;
+use crate::theme::{Theme, Tile};
+
+const CORNER_TOP_LEFT_CHAR: char = '╔';
+const CORNER_TOP_RIGHT_CHAR: char = '╗';
+const CORNER_BOT_LEFT_CHAR: char = '╚';
+const CORNER_BOT_RIGHT_CHAR: char = '╝';
+const BORDER_VERT_CHAR: char = '║';
+const BORDER_HORI_CHAR: char = '═';
+
+const TITLE: &str = " T E T I - R S ";
+
+// the panel holds next, hold and score, 6 cells wide and 12 rows tall.
+const PANEL_WIDTH: u16 = 12;
+const PANEL_HEIGHT: u16 = 12;
+// the stats column right of the panel, "label    value".
+const STATS_WIDTH: u16 = 16;
+
+// where the side panel ended up, it moves below the grid or disappears
+// when the terminal is too narrow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanelPlacement {
+    Right,
+    Below,
+    Hidden,
+}
+
+// absolute screen positions of everything, recomputed on every resize.
+#[derive(Clone, Copy, Debug)]
+pub struct Layout {
+    pub term_cols: u16,
+    pub term_rows: u16,
+    pub frame_x: u16,
+    pub frame_y: u16,
+    pub frame_width: u16,
+    pub frame_height: u16,
+    pub grid_x: u16,
+    pub grid_y: u16,
+    pub grid_width: u16,
+    pub grid_height: u16,
+    pub panel_x: u16,
+    pub panel_y: u16,
+    pub panel: PanelPlacement,
+    pub stats_x: u16,
+    pub show_stats: bool,
+    pub too_small: bool,
+}
+
+impl Layout {
+    pub fn new(term_cols: u16, term_rows: u16, num_rows: usize, num_cols: usize) -> Layout {
+        let grid_width = 2 * num_cols as u16;
+        let grid_height = num_rows as u16;
+
+        // (placement, stats, frame width, frame height) from most to least preferred.
+        let candidates = [
+            (PanelPlacement::Right, true, grid_width + PANEL_WIDTH + STATS_WIDTH + 8, grid_height + 4),
+            (PanelPlacement::Right, false, grid_width + PANEL_WIDTH + 6, grid_height + 4),
+            (PanelPlacement::Below, false, (grid_width + 4).max(PANEL_WIDTH + 4), grid_height + PANEL_HEIGHT + 3),
+            (PanelPlacement::Hidden, false, grid_width + 4, grid_height + 2),
+        ];
+        let fitting = candidates
+            .iter()
+            .find(|(_, _, width, height)| *width <= term_cols && *height <= term_rows);
+        let (panel, show_stats, frame_width, frame_height, too_small) = match fitting {
+            Some((panel, show_stats, width, height)) => (*panel, *show_stats, *width, *height, false),
+            None => {
+                let (panel, show_stats, width, height) = candidates[candidates.len() - 1];
+                (panel, show_stats, width, height, true)
+            }
+        };
+
+        let frame_x = term_cols.saturating_sub(frame_width) / 2;
+        let frame_y = term_rows.saturating_sub(frame_height) / 2;
+        let grid_x = frame_x + 2;
+        let grid_y = frame_y + 1;
+        let (panel_x, panel_y) = match panel {
+            PanelPlacement::Right => (grid_x + grid_width + 2, grid_y),
+            PanelPlacement::Below => (grid_x, grid_y + grid_height + 1),
+            PanelPlacement::Hidden => (grid_x, grid_y),
+        };
+        let stats_x = panel_x + PANEL_WIDTH + 2;
+
+        Layout {
+            term_cols,
+            term_rows,
+            frame_x,
+            frame_y,
+            frame_width,
+            frame_height,
+            grid_x,
+            grid_y,
+            grid_width,
+            grid_height,
+            panel_x,
+            panel_y,
+            panel,
+            stats_x,
+            show_stats,
+            too_small,
+        }
+    }
+
+    // the frame centred in what is left once `reserved` columns on the right are kept free.
+    pub fn with_reserved(term_cols: u16, term_rows: u16, num_rows: usize, num_cols: usize, reserved: u16) -> Layout {
+        let mut layout = Layout::new(term_cols.saturating_sub(reserved), term_rows, num_rows, num_cols);
+        layout.term_cols = term_cols;
+        layout
+    }
+
+    // one board per player side by side, each centred in its share of the width.
+    pub fn split(term_cols: u16, term_rows: u16, num_rows: usize, num_cols: usize, count: u16) -> Vec<Layout> {
+        let share = term_cols / count.max(1);
+        (0..count)
+            .map(|index| {
+                let mut layout = Layout::new(share, term_rows, num_rows, num_cols);
+                if layout.too_small {
+                    // the message spans the whole terminal and asks for room for every board.
+                    layout.term_cols = term_cols;
+                    layout.frame_width *= count;
+                    return layout;
+                }
+                let offset = index * share;
+                layout.frame_x += offset;
+                layout.grid_x += offset;
+                layout.panel_x += offset;
+                layout.stats_x += offset;
+                layout
+            })
+            .collect()
+    }
+}
+
+pub fn render_frame(stdout: &mut Stdout, theme: &Theme, layout: &Layout) {
+    render_frames(stdout, theme, &[*layout]);
+}
+
+pub fn render_frames(stdout: &mut Stdout, theme: &Theme, layouts: &[Layout]) {
+    execute!(stdout, Clear(ClearType::All)).unwrap();
+    if let Some(layout) = layouts.iter().find(|layout| layout.too_small) {
+        render_too_small(stdout, layout);
+        return;
+    }
+    for layout in layouts {
+        draw_frame(stdout, theme, layout);
+    }
+}
+
+fn draw_frame(stdout: &mut Stdout, theme: &Theme, layout: &Layout) {
+    // render background
+    {
+        for i in 0..layout.frame_width / 2 {
+            for j in 0..layout.frame_height {
+                let cell = get_cell(theme, &Tile::Background);
+                let row_pos = layout.frame_x + 2 * i;
+                execute!(stdout, cursor::MoveTo(row_pos, layout.frame_y + j), Print(cell)).unwrap();
+            }
+        }
+    }
+    // render frame
+    {
+        let row_start = layout.frame_x;
+        let row_end = layout.frame_x + layout.frame_width - 1;
+        let col_start = layout.frame_y;
+        let col_end = layout.frame_y + layout.frame_height - 1;
+
+        for i in row_start..=row_end {
+            let text = get_text(theme, &BORDER_HORI_CHAR.to_string(), &0);
+            execute!(stdout, cursor::MoveTo(i, col_start), Print(&text)).unwrap();
+            execute!(stdout, cursor::MoveTo(i, col_end), Print(&text)).unwrap();
+        }
+        for i in col_start..=col_end {
+            let text = get_text(theme, &BORDER_VERT_CHAR.to_string(), &0);
+            execute!(stdout, cursor::MoveTo(row_start, i), Print(&text)).unwrap();
+            execute!(stdout, cursor::MoveTo(row_end, i), Print(&text)).unwrap();
+        }
+
+        let text_corner_top_left = get_text(theme, &CORNER_TOP_LEFT_CHAR.to_string(), &2);
+        let text_corner_top_right = get_text(theme, &CORNER_TOP_RIGHT_CHAR.to_string(), &2);
+        let text_corner_bot_left = get_text(theme, &CORNER_BOT_LEFT_CHAR.to_string(), &2);
+        let text_corner_bot_right = get_text(theme, &CORNER_BOT_RIGHT_CHAR.to_string(), &2);
+
+        execute!(stdout, cursor::MoveTo(row_start, col_start), Print(&text_corner_top_left)).unwrap();
+        execute!(stdout, cursor::MoveTo(row_start, col_end), Print(&text_corner_bot_left)).unwrap();
+        execute!(stdout, cursor::MoveTo(row_end, col_start), Print(&text_corner_top_right)).unwrap();
+        execute!(stdout, cursor::MoveTo(row_end, col_end), Print(&text_corner_bot_right)).unwrap();
+    }
+    // render title, dropped when the frame is too narrow for it.
+    {
+        let text_raw = format!("{}{}{}", BORDER_VERT_CHAR, TITLE, BORDER_VERT_CHAR);
+        let title_width = text_raw.chars().count() as u16;
+        if title_width + 2 <= layout.frame_width {
+            let text = get_text(theme, &text_raw, &2);
+            let title_x = layout.frame_x + (layout.frame_width - title_width) / 2;
+            execute!(stdout, cursor::MoveTo(title_x, layout.frame_y), Print(&text)).unwrap();
+        }
+    }
+}
+
+// a box across the middle of the grid, used for game over and the like.
+pub fn render_message(stdout: &mut Stdout, theme: &Theme, layout: &Layout, lines: &[&str]) {
+    if layout.too_small {
+        return;
+    }
+    let width = layout.grid_width as usize;
+    let top = layout.grid_y + layout.grid_height.saturating_sub(lines.len() as u16) / 2;
+    for (index, line) in lines.iter().enumerate() {
+        let text: String = format!("{:^width$}", line, width = width).chars().take(width).collect();
+        let text = get_text(theme, &text, &1);
+        execute!(stdout, cursor::MoveTo(layout.grid_x, top + index as u16), Print(text)).unwrap();
+    }
+}
+
+fn render_too_small(stdout: &mut Stdout, layout: &Layout) {
+    let lines = [
+        "terminal too small".to_string(),
+        format!("need {}x{}", layout.frame_width, layout.frame_height),
+        format!("have {}x{}", layout.term_cols, layout.term_rows),
+    ];
+    for (index, line) in lines.iter().enumerate() {
+        let text: String = line.chars().take(layout.term_cols as usize).collect();
+        let x = layout.term_cols.saturating_sub(text.chars().count() as u16) / 2;
+        let y = (layout.term_rows / 2).saturating_sub(1) + index as u16;
+        execute!(stdout, cursor::MoveTo(x, y), Print(text)).unwrap();
+    }
+}
+
+// next and stored on top, score below them, stats in their own column.
+pub fn render_panel(stdout: &mut Stdout, theme: &Theme, layout: &Layout, game: &Game) {
+    if layout.too_small {
+        return;
+    }
+    if layout.panel == PanelPlacement::Hidden {
+        // no room for a panel, the score goes into the bottom border instead.
+        let text = get_text(theme, &format!(" {} ", game.score), &2);
+        let y = layout.frame_y + layout.frame_height - 1;
+        execute!(stdout, cursor::MoveTo(layout.frame_x + 2, y), Print(text)).unwrap();
+        return;
+    }
+
+    // render upcoming and stored.
+    {
+        clear_panel_rows(stdout, theme, layout, 0..=3);
+        render_preview(stdout, theme, layout.panel_x + 2, layout.panel_y + 1, &game.next_tetris);
+        clear_panel_rows(stdout, theme, layout, 5..=8);
+        if let Some(tet) = &game.saved_tetris {
+            render_preview(stdout, theme, layout.panel_x + 2, layout.panel_y + 6, tet);
+        }
+    }
+    // render score
+    {
+        clear_panel_rows(stdout, theme, layout, 10..=11);
+        let score_preface = get_text(theme, "score:", &1);
+        let score_string = get_text(theme, &game.score.to_string(), &1);
+        execute!(stdout, cursor::MoveTo(layout.panel_x + 2, layout.panel_y + 10), Print(score_preface)).unwrap();
+        execute!(stdout, cursor::MoveTo(layout.panel_x + 2, layout.panel_y + 11), Print(score_string)).unwrap();
+    }
+    // render stats
+    if layout.show_stats {
+        let summary = game.stats.summary(game.score);
+        let mut rows = summary.rows();
+        let last_clear = game.last_clear.map(|clear| clear.name()).unwrap_or_default();
+        rows.insert(0, ("last", last_clear.to_string()));
+        if let Some(fault) = &game.last_fault {
+            // the piece, then keys used against the fewest it needed.
+            rows.insert(1, ("fault", format!("{:?} {}/{}", fault.kind, fault.used.len(), fault.optimal.len())));
+            rows.insert(2, ("fix", finesse::notation(&fault.optimal)));
+        }
+        let num_rows = layout.grid_height as usize;
+        for index in 0..num_rows {
+            let text = match rows.get(index) {
+                // long values like "t-spin single" push the label out.
+                Some((label, value)) if label.len() + value.len() < STATS_WIDTH as usize => {
+                    format!("{:<width$}{}", label, value, width = STATS_WIDTH as usize - value.len())
+                }
+                Some((_, value)) => format!("{:>width$.width$}", value, width = STATS_WIDTH as usize),
+                None => String::new(),
+            };
+            let text = get_text(theme, &format!("{:<width$}", text, width = STATS_WIDTH as usize), &0);
+            execute!(stdout, cursor::MoveTo(layout.stats_x, layout.grid_y + index as u16), Print(text)).unwrap();
+        }
+    }
+}
+
+// a short status such as the lines left, right aligned in the bottom border.
+pub fn render_status(stdout: &mut Stdout, theme: &Theme, layout: &Layout, status: &str) {
+    if layout.too_small {
+        return;
+    }
+    // half the border, the other half is left for the score of a hidden panel.
+    let width = (layout.frame_width as usize).saturating_sub(4) / 2;
+    let text: String = format!(" {} ", status).chars().take(width).collect();
+    let border = BORDER_HORI_CHAR.to_string().repeat(width - text.chars().count());
+    let x = layout.frame_x + layout.frame_width - 2 - width as u16;
+    let y = layout.frame_y + layout.frame_height - 1;
+    execute!(stdout, cursor::MoveTo(x, y), Print(get_text(theme, &border, &0)), Print(get_text(theme, &text, &2))).unwrap();
+}
+
+// incoming garbage as a bar rising from the bottom, between the frame and the grid.
+pub fn render_garbage_meter(stdout: &mut Stdout, theme: &Theme, layout: &Layout, lines: u32) {
+    if layout.too_small {
+        return;
+    }
+    let half_glyph = |glyph: &str| glyph.chars().next().unwrap_or(' ').to_string();
+    let empty = theme.paint(&half_glyph(&theme.background_glyph), theme.background, None);
+    let filled = theme.paint(&half_glyph(&theme.garbage_glyph), theme.garbage, None);
+    for row in 0..layout.grid_height {
+        let cell = match (layout.grid_height - row) as u32 <= lines {
+            true => &filled,
+            false => &empty,
+        };
+        execute!(stdout, cursor::MoveTo(layout.grid_x - 1, layout.grid_y + row), Print(cell)).unwrap();
+    }
+}
+
+// columns a mini board takes, the border included.
+pub fn mini_board_width(num_cols: usize) -> u16 {
+    num_cols as u16 + 2
+}
+
+// another player's board at one character per cell, boxed with a label in the top border.
+pub fn render_mini_board(stdout: &mut Stdout, theme: &Theme, x: u16, y: u16, label: &str, rows: &[Vec<Cell>]) {
+    let width = rows.first().map_or(0, |row| row.len());
+    let label: String = label.chars().take(width).collect();
+    let top = format!("┌{:─<width$}┐", label, width = width);
+    execute!(stdout, cursor::MoveTo(x, y), Print(get_text(theme, &top, &0))).unwrap();
+    let half_glyph = |glyph: &str| glyph.chars().next().unwrap_or(' ').to_string();
+    for (index, row) in rows.iter().enumerate() {
+        let mut line = get_text(theme, "│", &0);
+        for cell in row {
+            line += &match Tile::from(*cell) {
+                Tile::Piece(kind) => theme.paint(&half_glyph(&theme.block_glyph), theme.piece(kind), None),
+                Tile::Garbage => theme.paint(&half_glyph(&theme.garbage_glyph), theme.garbage, None),
+                _ => theme.paint(&half_glyph(&theme.empty_glyph), theme.empty, None),
+            };
+        }
+        line += &get_text(theme, "│", &0);
+        execute!(stdout, cursor::MoveTo(x, y + 1 + index as u16), Print(line)).unwrap();
+    }
+    let bottom = format!("└{:─<width$}┘", "", width = width);
+    execute!(stdout, cursor::MoveTo(x, y + 1 + rows.len() as u16), Print(get_text(theme, &bottom, &0))).unwrap();
+}
+
+// a tetris in its spawn orientation with its top left at (x, y).
+fn render_preview(stdout: &mut Stdout, theme: &Theme, x: u16, y: u16, tetris: &Tetris) {
+    for row_col in tetris.get_tiles() {
+        let cell_x = x + 2 * row_col.col as u16;
+        let cell_y = y + row_col.row as u16;
+        execute!(stdout, cursor::MoveTo(cell_x, cell_y), Print(get_cell(theme, &Tile::Piece(tetris.kind)))).unwrap();
+    }
+}
+
+fn clear_panel_rows(stdout: &mut Stdout, theme: &Theme, layout: &Layout, rows: std::ops::RangeInclusive<u16>) {
+    for j in rows {
+        for i in 0..PANEL_WIDTH / 2 {
+            let cell = get_cell(theme, &Tile::Empty);
+            execute!(stdout, cursor::MoveTo(layout.panel_x + 2 * i, layout.panel_y + j), Print(cell)).unwrap();
+        }
+    }
+}
+
+// the grid with the falling tetris and its shadow on top.
+pub fn render_grid(stdout: &mut Stdout, theme: &Theme, layout: &Layout, grid: &Grid, cur_tetris: &Tetris, hint: Option<&Tetris>) {
+    if layout.too_small {
+        return;
+    }
+    let rendering_grid_vec: Vec<Vec<Tile>> = grid.grid_vec
+        .iter()
+        .map(|row| row.iter().map(|cell| Tile::from(*cell)).collect())
+        .collect();
+    draw_with_tetris(stdout, theme, layout, rendering_grid_vec, grid, cur_tetris, hint);
+}
+
+// the grid as `render_grid` draws it, with the cells of `target` it does not have yet drawn faintly.
+pub fn render_target_grid(stdout: &mut Stdout, theme: &Theme, layout: &Layout, grid: &Grid, cur_tetris: &Tetris, hint: Option<&Tetris>, target: &Grid) {
+    if layout.too_small {
+        return;
+    }
+    let rendering_grid_vec: Vec<Vec<Tile>> = grid.grid_vec
+        .iter()
+        .zip(&target.grid_vec)
+        .map(|(row, target_row)| {
+            row.iter()
+                .zip(target_row)
+                .map(|(cell, target)| match (cell, target) {
+                    (Cell::Empty, Cell::Piece(kind)) => Tile::Target(*kind),
+                    _ => Tile::from(*cell),
+                })
+                .collect()
+        })
+        .collect();
+    draw_with_tetris(stdout, theme, layout, rendering_grid_vec, grid, cur_tetris, hint);
+}
+
+fn draw_with_tetris(stdout: &mut Stdout, theme: &Theme, layout: &Layout, mut rendering_grid_vec: Vec<Vec<Tile>>, grid: &Grid, cur_tetris: &Tetris, hint: Option<&Tetris>) {
+    // under the shadow, so lining the piece up with the hint hides it.
+    if let Some(hint) = hint {
+        for row_col in &hint.get_poses() {
+            *rendering_grid_vec.get_mut(row_col.row).unwrap().get_mut(row_col.col).unwrap() = Tile::Hint;
+        }
+    }
+    let shadow = cur_tetris.get_droped_tetris(&grid.grid_vec);
+    for row_col in &shadow.get_poses() {
+        *rendering_grid_vec.get_mut(row_col.row).unwrap().get_mut(row_col.col).unwrap() = Tile::Ghost;
+    }
+    for row_col in &cur_tetris.get_poses() {
+        *rendering_grid_vec.get_mut(row_col.row).unwrap().get_mut(row_col.col).unwrap() = Tile::Piece(cur_tetris.kind);
+    }
+    draw_tiles(stdout, theme, layout, &rendering_grid_vec);
+}
+
+// just the cells, with no piece falling, for the editor.
+pub fn render_board(stdout: &mut Stdout, theme: &Theme, layout: &Layout, rows: &[Vec<Cell>]) {
+    if layout.too_small {
+        return;
+    }
+    let tiles: Vec<Vec<Tile>> = rows.iter().map(|row| row.iter().map(|cell| Tile::from(*cell)).collect()).collect();
+    draw_tiles(stdout, theme, layout, &tiles);
+}
+
+// brackets over one grid cell, in the colour of `cell`.
+pub fn render_cursor(stdout: &mut Stdout, theme: &Theme, layout: &Layout, row: usize, col: usize, cell: Cell) {
+    if layout.too_small {
+        return;
+    }
+    let text = match cell {
+        Cell::Piece(kind) => theme.paint("[]", theme.piece(kind), None),
+        Cell::Garbage => theme.paint("[]", theme.garbage, None),
+        Cell::Empty => get_text(theme, "[]", &1),
+    };
+    execute!(stdout, cursor::MoveTo(layout.grid_x + 2 * col as u16, layout.grid_y + row as u16), Print(text)).unwrap();
+}
+
+fn draw_tiles(stdout: &mut Stdout, theme: &Theme, layout: &Layout, rendering_grid_vec: &[Vec<Tile>]) {
+    for (index, row) in rendering_grid_vec.iter().enumerate() {
+        let mut row_string_vec: Vec<String> = vec![];
+        for cell in row.iter() {
+            row_string_vec.push(get_cell(theme, cell));
+        }
+        let row_string = row_string_vec.join("");
+        execute!(stdout, cursor::MoveTo(layout.grid_x, layout.grid_y + index as u16), Print(row_string)).unwrap();
+    }
+}
+
+pub fn get_cell(theme: &Theme, tile: &Tile) -> String {
+    match tile {
+        // background
+        Tile::Empty => theme.paint(&theme.empty_glyph, theme.empty, None),
+        Tile::Background => theme.paint(&theme.background_glyph, theme.background, None),
+
+        // tetris
+        Tile::Piece(kind) => theme.paint(&theme.block_glyph, theme.piece(*kind), None),
+        Tile::Garbage => theme.paint(&theme.garbage_glyph, theme.garbage, None),
+        // tetris shadow
+        Tile::Ghost => theme.paint(&theme.ghost_glyph, theme.ghost, None),
+        // suggested placement
+        Tile::Hint => theme.paint(&theme.hint_glyph, theme.hint, None),
+        // what is left of an opener
+        Tile::Target(kind) => theme.paint(&theme.hint_glyph, theme.piece(*kind), None),
+    }
+}
+
+pub fn get_text(theme: &Theme, text: &str, color: &usize) -> String {
+    match color {
+        // background
+        0 => theme.paint(text, theme.frame, Some(theme.frame_bg)),
+        1 => theme.paint(text, theme.text, Some(theme.text_bg)),
+        2 => theme.paint_bold(text, theme.frame, Some(theme.frame_bg)),
+
+        _ => theme.paint(text, theme.frame, None),
+    }
+}
+
+// `text` in lines of at most `width`, broken between words where it can be.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => {
+                let chars: Vec<char> = word.chars().collect();
+                lines.extend(chars.chunks(width.max(1)).map(|chunk| chunk.iter().collect()));
+            }
+        }
+    }
+    lines
+}
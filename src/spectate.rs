@@ -0,0 +1,322 @@
+use std::io::{BufRead, BufReader, Stdout, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{poll, read, Event, KeyEventKind};
+use crossterm::terminal::size as terminal_size;
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::{Action, Bindings};
+use crate::builder::build;
+use crate::config::{Config, Ruleset};
+use crate::game::Game;
+use crate::models::cell::PieceKind;
+use crate::models::tetris::Tetris;
+use crate::net;
+use crate::render::{render_frame, render_grid, render_message, render_panel, Layout};
+use crate::stats::{Clear, Stats};
+use crate::terminal::TerminalGuard;
+use crate::theme::Theme;
+
+// bumped whenever a message changes, viewers refuse other versions.
+pub const PROTOCOL_VERSION: u32 = 1;
+// a viewer that cannot keep up is dropped rather than holding up the game.
+const WRITE_TIMEOUT_MS: u64 = 200;
+// the clock alone changes every frame, that is only worth sending this often.
+const CLOCK_MS: u64 = 100;
+
+// one json object per line, from the game to its viewers.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SpectateMessage {
+    Hello {
+        version: u32,
+        width: usize,
+        height: usize,
+    },
+    Snapshot(Snapshot),
+}
+
+// everything the renderer shows of a game.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct Snapshot {
+    // locked cells, rows from the top, one digit per cell as in `Cell::code`.
+    board: Vec<String>,
+    piece: PieceState,
+    next: String,
+    hold: Option<String>,
+    score: i32,
+    stats: Stats,
+    last_clear: Option<Clear>,
+    game_over: bool,
+}
+
+// the falling piece by its spin and its shift from the spawn position.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct PieceState {
+    kind: String,
+    spin: i32,
+    row: usize,
+    col: usize,
+}
+
+impl Snapshot {
+    fn of(game: &Game) -> Snapshot {
+        let (row, col) = game.cur_tetris.get_shift();
+        Snapshot {
+            board: game.grid.grid_vec
+                .iter()
+                .map(|row| row.iter().map(|cell| char::from(b'0' + cell.code())).collect())
+                .collect(),
+            piece: PieceState {
+                kind: game.cur_tetris.kind.name().to_string(),
+                spin: game.cur_tetris.get_spin(),
+                row,
+                col,
+            },
+            next: game.next_tetris.kind.name().to_string(),
+            hold: game.saved_tetris.as_ref().map(|tetris| tetris.kind.name().to_string()),
+            score: game.score,
+            stats: game.stats.clone(),
+            last_clear: game.last_clear,
+            game_over: game.game_over,
+        }
+    }
+
+    // copies the snapshot onto a game of the same size, false if it does not fit.
+    fn apply(&self, game: &mut Game) -> bool {
+        let rows = net::parse_rows(&self.board);
+        let num_cols = game.grid.grid_vec.first().map_or(0, |row| row.len());
+        if rows.len() != game.grid.grid_vec.len() || rows.iter().any(|row| row.len() != num_cols) {
+            return false;
+        }
+        let Some(mut piece) = tetris(&self.piece.kind) else {
+            return false;
+        };
+        piece.place(self.piece.spin, self.piece.row, self.piece.col);
+        if !piece.get_poses().iter().all(|row_col| row_col.row < rows.len() && row_col.col < num_cols) {
+            return false;
+        }
+        let Some(next) = tetris(&self.next) else {
+            return false;
+        };
+        game.grid.grid_vec = rows;
+        game.cur_tetris = piece;
+        game.next_tetris = next;
+        game.saved_tetris = self.hold.as_deref().and_then(tetris);
+        game.score = self.score;
+        game.stats = self.stats.clone();
+        game.last_clear = self.last_clear;
+        game.game_over = self.game_over;
+        true
+    }
+}
+
+fn tetris(name: &str) -> Option<Tetris> {
+    PieceKind::from_name(name).map(|kind| build::build_tetris(kind, 0, 0))
+}
+
+// the viewers and the last snapshot, shared between the game and the thread taking new viewers.
+#[derive(Default)]
+struct Viewers {
+    streams: Vec<TcpStream>,
+    hello: String,
+    latest: Option<String>,
+}
+
+impl Viewers {
+    // sends `line` to every viewer, dropping the ones that fail.
+    fn broadcast(&mut self, line: &str) {
+        self.streams.retain_mut(|stream| writeln!(stream, "{}", line).is_ok());
+    }
+}
+
+// streams a game to any number of viewers, late ones start from the latest snapshot.
+pub struct SpectateServer {
+    lines: Sender<String>,
+    last: Option<Snapshot>,
+    last_sent: Instant,
+}
+
+impl SpectateServer {
+    pub fn bind(address: &str, width: usize, height: usize) -> Result<SpectateServer, String> {
+        let listener = TcpListener::bind(address).map_err(|err| format!("{}: {}", address, err))?;
+        let hello = SpectateMessage::Hello {
+            version: PROTOCOL_VERSION,
+            width,
+            height,
+        };
+        let viewers = Arc::new(Mutex::new(Viewers {
+            hello: serde_json::to_string(&hello).unwrap(),
+            ..Viewers::default()
+        }));
+
+        let accepting = Arc::clone(&viewers);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let _ = stream.set_nodelay(true);
+                let _ = stream.set_write_timeout(Some(Duration::from_millis(WRITE_TIMEOUT_MS)));
+                let mut viewers = accepting.lock().unwrap();
+                let mut greeted = writeln!(stream, "{}", viewers.hello).is_ok();
+                if let Some(latest) = &viewers.latest {
+                    greeted &= writeln!(stream, "{}", latest).is_ok();
+                }
+                if greeted {
+                    viewers.streams.push(stream);
+                }
+            }
+        });
+
+        // writes happen off the game loop so a slow viewer never stalls a frame.
+        let (lines, receiver): (Sender<String>, Receiver<String>) = mpsc::channel();
+        thread::spawn(move || {
+            for line in receiver {
+                let mut viewers = viewers.lock().unwrap();
+                viewers.broadcast(&line);
+                viewers.latest = Some(line);
+            }
+        });
+        Ok(SpectateServer {
+            lines,
+            last: None,
+            last_sent: Instant::now(),
+        })
+    }
+
+    // sends the game to the viewers if anything they can see changed.
+    pub fn update(&mut self, game: &Game) {
+        let snapshot = Snapshot::of(game);
+        if let Some(last) = &self.last {
+            let mut ticked = last.clone();
+            ticked.stats.elapsed_ms = snapshot.stats.elapsed_ms;
+            if ticked == snapshot && self.last_sent.elapsed() < Duration::from_millis(CLOCK_MS) {
+                return;
+            }
+        }
+        let line = serde_json::to_string(&SpectateMessage::Snapshot(snapshot.clone())).unwrap();
+        let _ = self.lines.send(line);
+        self.last = Some(snapshot);
+        self.last_sent = Instant::now();
+    }
+}
+
+// a connection to a spectate server, past the hello.
+pub struct Viewer {
+    pub width: usize,
+    pub height: usize,
+    snapshots: Receiver<Result<Snapshot, String>>,
+}
+
+pub fn connect(address: &str) -> Result<Viewer, String> {
+    let stream = TcpStream::connect(address).map_err(|err| format!("{}: {}", address, err))?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|err| format!("{}: {}", address, err))?;
+    let (width, height) = match serde_json::from_str(&line) {
+        Ok(SpectateMessage::Hello { version, width, height }) if version == PROTOCOL_VERSION => (width, height),
+        Ok(SpectateMessage::Hello { version, .. }) => {
+            return Err(format!("the game speaks protocol version {}, this is version {}", version, PROTOCOL_VERSION))
+        }
+        _ => return Err(format!("{}: not a tetri-rs spectate server", address)),
+    };
+    let mut config = Config::default();
+    config.ruleset.width = width;
+    config.ruleset.height = height;
+    config.validate().map_err(|err| format!("{}: the game sent a bad board: {}", address, err))?;
+
+    let (sender, snapshots) = mpsc::channel();
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let snapshot = match line.map_err(|err| err.to_string()).and_then(|line| {
+                serde_json::from_str(&line).map_err(|err| format!("bad message: {}", err))
+            }) {
+                Ok(SpectateMessage::Snapshot(snapshot)) => Ok(snapshot),
+                Ok(SpectateMessage::Hello { .. }) => continue,
+                Err(err) => Err(err),
+            };
+            let failed = snapshot.is_err();
+            if sender.send(snapshot).is_err() || failed {
+                return;
+            }
+        }
+        let _ = sender.send(Err("the game closed the connection".to_string()));
+    });
+    Ok(Viewer {
+        width,
+        height,
+        snapshots,
+    })
+}
+
+// draws someone else's game until it ends or the viewer quits, only quit does anything.
+pub fn watch(
+    stdout: &mut Stdout,
+    terminal: &TerminalGuard,
+    theme: &Theme,
+    bindings: &Bindings,
+    frame_ms: u64,
+    viewer: &Viewer,
+) -> Option<String> {
+    let (width, height) = (viewer.width, viewer.height);
+    let ruleset = Ruleset {
+        width,
+        height,
+        ..Ruleset::default()
+    };
+    let mut game = Game::new(&ruleset, 0);
+    let mut waiting = true;
+    let duration = Duration::from_millis(frame_ms);
+    let (term_cols, term_rows) = terminal_size().unwrap();
+    let mut layout = Layout::new(term_cols, term_rows, height, width);
+    render_frame(stdout, theme, &layout);
+
+    loop {
+        if terminal.quit_requested() {
+            return None;
+        }
+        let mut ended = None;
+        for snapshot in viewer.snapshots.try_iter() {
+            match snapshot {
+                Ok(snapshot) if snapshot.apply(&mut game) => waiting = false,
+                Ok(_) => ended = Some("the game sent a board that does not fit".to_string()),
+                Err(err) => ended = Some(err),
+            }
+            if ended.is_some() {
+                break;
+            }
+        }
+        if !waiting {
+            render_panel(stdout, theme, &layout, &game);
+            render_grid(stdout, theme, &layout, &game.grid, &game.cur_tetris, None);
+        }
+        if let Some(reason) = ended {
+            let status = if game.game_over { "G A M E  O V E R" } else { "E N D E D" };
+            render_message(stdout, theme, &layout, &[status, "", "press any key"]);
+            terminal.wait_for_key();
+            return Some(reason);
+        }
+        if waiting {
+            render_message(stdout, theme, &layout, &["waiting for", "the game"]);
+        }
+
+        if poll(duration).unwrap() {
+            match read().unwrap() {
+                Event::Resize(term_cols, term_rows) => {
+                    layout = Layout::new(term_cols, term_rows, height, width);
+                    render_frame(stdout, theme, &layout);
+                }
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press && bindings.action(&key_event) == Some(Action::Quit) => {
+                    return None;
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+// what a single lock did to the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clear {
+    pub lines: u32,
+    pub t_spin: bool,
+    pub perfect_clear: bool,
+    // consecutive clearing pieces before this one, 0 for the first clear.
+    pub combo: u32,
+    // this and the previous clear were both tetrises or t-spins.
+    pub back_to_back: bool,
+}
+
+impl Clear {
+    // tetrises and line clearing t-spins keep back to back alive.
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || (self.t_spin && self.lines > 0)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match (self.t_spin, self.lines) {
+            (true, 0) => "t-spin",
+            (true, 1) => "t-spin single",
+            (true, 2) => "t-spin double",
+            (true, _) => "t-spin triple",
+            (false, 0) => "",
+            (false, 1) => "single",
+            (false, 2) => "double",
+            (false, 3) => "triple",
+            (false, _) => "tetris",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClearCounts {
+    pub single: u32,
+    pub double: u32,
+    pub triple: u32,
+    pub tetris: u32,
+    pub t_spin: u32,
+    pub t_spin_single: u32,
+    pub t_spin_double: u32,
+    pub t_spin_triple: u32,
+    pub perfect_clear: u32,
+    pub back_to_back: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    pub pieces: u32,
+    pub keys: u32,
+    pub lines: u32,
+    pub attack: u32,
+    pub max_combo: u32,
+    pub finesse_faults: u32,
+    pub elapsed_ms: u64,
+    pub clears: ClearCounts,
+}
+
+// everything the panel and the summary show, rates included.
+#[derive(Clone, Debug, Serialize)]
+pub struct StatsSummary {
+    pub score: i32,
+    pub pieces: u32,
+    pub keys: u32,
+    pub lines: u32,
+    pub attack: u32,
+    pub max_combo: u32,
+    pub finesse_faults: u32,
+    pub seconds: f64,
+    pub pps: f64,
+    pub kpp: f64,
+    pub lpm: f64,
+    pub apm: f64,
+    pub clears: ClearCounts,
+}
+
+impl Stats {
+    pub fn record_key(&mut self) {
+        self.keys += 1;
+    }
+
+    pub fn record_lock(&mut self, clear: &Clear, attack: u32) {
+        self.pieces += 1;
+        self.lines += clear.lines;
+        self.attack += attack;
+        if clear.lines > 0 {
+            self.max_combo = self.max_combo.max(clear.combo);
+        }
+
+        let counts = &mut self.clears;
+        match (clear.t_spin, clear.lines) {
+            (true, 0) => counts.t_spin += 1,
+            (true, 1) => counts.t_spin_single += 1,
+            (true, 2) => counts.t_spin_double += 1,
+            (true, _) => counts.t_spin_triple += 1,
+            (false, 0) => (),
+            (false, 1) => counts.single += 1,
+            (false, 2) => counts.double += 1,
+            (false, 3) => counts.triple += 1,
+            (false, _) => counts.tetris += 1,
+        }
+        if clear.perfect_clear {
+            counts.perfect_clear += 1;
+        }
+        if clear.back_to_back {
+            counts.back_to_back += 1;
+        }
+    }
+
+    pub fn summary(&self, score: i32) -> StatsSummary {
+        let seconds = self.elapsed_ms as f64 / 1000.0;
+        let minutes = seconds / 60.0;
+        let per = |count: u32, over: f64| if over > 0.0 { count as f64 / over } else { 0.0 };
+        StatsSummary {
+            score,
+            pieces: self.pieces,
+            keys: self.keys,
+            lines: self.lines,
+            attack: self.attack,
+            max_combo: self.max_combo,
+            finesse_faults: self.finesse_faults,
+            seconds,
+            pps: per(self.pieces, seconds),
+            kpp: per(self.keys, self.pieces as f64),
+            lpm: per(self.lines, minutes),
+            apm: per(self.attack, minutes),
+            clears: self.clears.clone(),
+        }
+    }
+}
+
+impl StatsSummary {
+    // label, value pairs in the order they are shown.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("time", format!("{:.1}s", self.seconds)),
+            ("pieces", self.pieces.to_string()),
+            ("pps", format!("{:.2}", self.pps)),
+            ("kpp", format!("{:.2}", self.kpp)),
+            ("lpm", format!("{:.1}", self.lpm)),
+            ("apm", format!("{:.1}", self.apm)),
+            ("max combo", self.max_combo.to_string()),
+            ("faults", self.finesse_faults.to_string()),
+            ("single", self.clears.single.to_string()),
+            ("double", self.clears.double.to_string()),
+            ("triple", self.clears.triple.to_string()),
+            ("tetris", self.clears.tetris.to_string()),
+            ("tss", self.clears.t_spin_single.to_string()),
+            ("tsd", self.clears.t_spin_double.to_string()),
+            ("tst", self.clears.t_spin_triple.to_string()),
+            ("pc", self.clears.perfect_clear.to_string()),
+            ("b2b", self.clears.back_to_back.to_string()),
+        ]
+    }
+}
+
@@ -0,0 +1,318 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bot::Brain;
+use crate::game::{Game, Input};
+use crate::models::cell::{Cell, PieceKind};
+use crate::models::tetris::Tetris;
+use crate::movegen;
+
+// time an external bot gets to introduce itself and accept the rules.
+const HANDSHAKE_MS: u64 = 5000;
+// bots expect a 40 row board, taller than what is shown.
+const BOARD_ROWS: usize = 40;
+
+// messages the game sends, one json object per line.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FrontendMessage {
+    Rules {
+        randomizer: &'static str,
+    },
+    Start {
+        hold: Option<&'static str>,
+        queue: Vec<&'static str>,
+        combo: u32,
+        back_to_back: bool,
+        board: Vec<Vec<Option<&'static str>>>,
+    },
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        mv: Move,
+    },
+    NewPiece {
+        piece: &'static str,
+    },
+    Stop,
+    Quit,
+}
+
+// messages the bot sends, anything else is skipped.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BotMessage {
+    Info {
+        name: String,
+        version: String,
+    },
+    Ready,
+    Error {
+        reason: String,
+    },
+    Suggestion {
+        moves: Vec<Move>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Move {
+    location: Location,
+    #[serde(default)]
+    spin: Spin,
+}
+
+// a piece by its rotation centre, `y` counts up from the floor.
+#[derive(Clone, Serialize, Deserialize)]
+struct Location {
+    #[serde(rename = "type")]
+    kind: String,
+    orientation: Orientation,
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Orientation {
+    North,
+    East,
+    South,
+    West,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Spin {
+    #[default]
+    None,
+    Mini,
+    Full,
+}
+
+// a bot in a child process speaking the tetris bot protocol.
+pub struct TbpBrain {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    move_ms: u64,
+    started: bool,
+    // the dealt pieces and locks the bot knows of, anything else means it has to start over.
+    dealt: u32,
+    pieces: u32,
+}
+
+impl TbpBrain {
+    pub fn spawn(command: &[String], move_ms: u64) -> Result<TbpBrain, String> {
+        let (program, args) = command.split_first().ok_or_else(|| "bot.external is empty".to_string())?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // the terminal belongs to the game.
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("{}: {}", program, err))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut brain = TbpBrain {
+            child,
+            stdin,
+            lines,
+            move_ms,
+            started: false,
+            dealt: 0,
+            pieces: 0,
+        };
+        match brain.receive(HANDSHAKE_MS)? {
+            BotMessage::Info { name, version } if !name.is_empty() && !version.is_empty() => (),
+            _ => return Err(format!("{}: expected an info message", program)),
+        }
+        brain.send(&FrontendMessage::Rules { randomizer: "unknown" })?;
+        match brain.receive(HANDSHAKE_MS)? {
+            BotMessage::Ready => Ok(brain),
+            BotMessage::Error { reason } => Err(format!("{}: {}", program, reason)),
+            _ => Err(format!("{}: expected a ready message", program)),
+        }
+    }
+
+    fn send(&mut self, message: &FrontendMessage) -> Result<(), String> {
+        let line = serde_json::to_string(message).unwrap();
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|err| format!("bot stopped listening: {}", err))
+    }
+
+    // the next message the bot sends within `millis`.
+    fn receive(&mut self, millis: u64) -> Result<BotMessage, String> {
+        let deadline = Instant::now() + Duration::from_millis(millis);
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(left) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => return Err(format!("bot took longer than {}ms", millis)),
+                Err(RecvTimeoutError::Disconnected) => return Err("bot exited".to_string()),
+            };
+            match serde_json::from_str(&line) {
+                Ok(BotMessage::Other) => (),
+                Ok(message) => return Ok(message),
+                Err(err) => return Err(format!("bot sent bad json: {}", err)),
+            }
+        }
+    }
+
+    // brings the bot up to date, starting over if it missed a lock.
+    fn sync(&mut self, game: &Game) -> Result<(), String> {
+        if self.started && self.pieces == game.stats.pieces {
+            // a hold with nothing held deals two pieces, and they are the last two in the queue.
+            let new_pieces = [game.cur_tetris.kind, game.next_tetris.kind];
+            let count = (game.dealt - self.dealt) as usize;
+            for kind in &new_pieces[new_pieces.len().saturating_sub(count)..] {
+                self.send(&FrontendMessage::NewPiece { piece: kind.name() })?;
+            }
+        } else {
+            if self.started {
+                self.send(&FrontendMessage::Stop)?;
+            }
+            self.send(&start_message(game))?;
+            self.started = true;
+        }
+        self.dealt = game.dealt;
+        self.pieces = game.stats.pieces;
+        Ok(())
+    }
+
+    // the engine's inputs for a move, none if the move cannot be made.
+    fn inputs_for(&self, game: &Game, mv: &Move) -> Option<Vec<Input>> {
+        let held = game.saved_tetris.as_ref().unwrap_or(&game.next_tetris);
+        let (mut inputs, tetris) = match PieceKind::from_name(&mv.location.kind)? {
+            kind if kind == game.cur_tetris.kind => (vec![], &game.cur_tetris),
+            kind if kind == held.kind => (vec![Input::Hold], held),
+            _ => return None,
+        };
+        let num_rows = game.grid.grid_vec.len();
+        let num_cols = game.grid.grid_vec.first().map_or(0, |row| row.len());
+        let target = location_cells(&mv.location, num_rows, num_cols)?;
+        let t_spin = mv.spin == Spin::Full;
+        let mut placements: Vec<movegen::Placement> = movegen::placements(&game.grid, tetris, true)
+            .into_iter()
+            .filter(|placement| placement.cells() == target)
+            .collect();
+        // a spin the bot asked for wins over a plain drop into the same cells.
+        placements.sort_by_key(|placement| placement.t_spin != t_spin);
+        inputs.extend(placements.into_iter().next()?.inputs);
+        Some(inputs)
+    }
+}
+
+impl Brain for TbpBrain {
+    fn choose(&mut self, game: &Game) -> Result<Vec<Input>, String> {
+        self.sync(game)?;
+        self.send(&FrontendMessage::Suggest)?;
+        let moves = loop {
+            if let BotMessage::Suggestion { moves } = self.receive(self.move_ms)? {
+                break moves;
+            }
+        };
+        for mv in moves {
+            if let Some(inputs) = self.inputs_for(game, &mv) {
+                self.send(&FrontendMessage::Play { mv })?;
+                self.pieces += 1;
+                return Ok(inputs);
+            }
+        }
+        Err("bot suggested no legal move".to_string())
+    }
+}
+
+impl Drop for TbpBrain {
+    fn drop(&mut self) {
+        let _ = self.send(&FrontendMessage::Quit);
+        // a moment to quit on its own before it is killed.
+        let deadline = Instant::now() + Duration::from_millis(100);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn start_message(game: &Game) -> FrontendMessage {
+    let num_cols = game.grid.grid_vec.first().map_or(0, |row| row.len());
+    let mut board: Vec<Vec<Option<&'static str>>> = game.grid.grid_vec
+        .iter()
+        .rev()
+        .map(|row| row.iter().map(|cell| cell_name(*cell)).collect())
+        .collect();
+    while board.len() < BOARD_ROWS {
+        board.push(vec![None; num_cols]);
+    }
+    FrontendMessage::Start {
+        hold: game.saved_tetris.as_ref().map(|tetris: &Tetris| tetris.kind.name()),
+        queue: vec![game.cur_tetris.kind.name(), game.next_tetris.kind.name()],
+        combo: game.combo(),
+        back_to_back: game.back_to_back(),
+        board,
+    }
+}
+
+fn cell_name(cell: Cell) -> Option<&'static str> {
+    match cell {
+        Cell::Empty => None,
+        Cell::Garbage => Some("G"),
+        Cell::Piece(kind) => Some(kind.name()),
+    }
+}
+
+// (x, y) of each cell around the rotation centre, pointing north.
+fn north_offsets(kind: PieceKind) -> [(i32, i32); 4] {
+    match kind {
+        PieceKind::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        PieceKind::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        PieceKind::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+        PieceKind::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        PieceKind::Z => [(-1, 1), (0, 1), (0, 0), (1, 0)],
+        PieceKind::J => [(-1, 1), (-1, 0), (0, 0), (1, 0)],
+        PieceKind::L => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+    }
+}
+
+// the grid cells a location covers, sorted like `movegen::cells`, none if any is off the grid.
+fn location_cells(location: &Location, num_rows: usize, num_cols: usize) -> Option<Vec<(usize, usize)>> {
+    let kind = PieceKind::from_name(&location.kind)?;
+    let mut cells = vec![];
+    for (x, y) in north_offsets(kind) {
+        let (x, y) = match location.orientation {
+            Orientation::North => (x, y),
+            Orientation::East => (y, -x),
+            Orientation::South => (-x, -y),
+            Orientation::West => (-y, x),
+        };
+        let col = usize::try_from(location.x + x).ok().filter(|col| *col < num_cols)?;
+        let up = usize::try_from(location.y + y).ok().filter(|up| *up < num_rows)?;
+        cells.push((num_rows - 1 - up, col));
+    }
+    cells.sort();
+    Some(cells)
+}
@@ -0,0 +1,108 @@
+use std::io::{self, stdout};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Once};
+use std::time::Duration;
+
+use crossterm::cursor;
+use crossterm::event::{poll, read, Event, KeyEventKind};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+
+static PANIC_HOOK: Once = Once::new();
+
+// owns the terminal while the game runs, dropping it always gives the
+// terminal back the way the shell left it.
+pub struct TerminalGuard {
+    quit_requested: Arc<AtomicBool>,
+    signal_ids: Vec<SignalId>,
+}
+
+impl TerminalGuard {
+    pub fn new() -> io::Result<TerminalGuard> {
+        // restore before the default hook prints, otherwise the message is
+        // lost in the alternate screen and mangled by raw mode.
+        PANIC_HOOK.call_once(|| {
+            let default_hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                restore();
+                default_hook(info);
+            }));
+        });
+
+        let quit_requested = Arc::new(AtomicBool::new(false));
+        let signal_ids = register_signals(&quit_requested)?;
+
+        let guard = TerminalGuard { quit_requested, signal_ids };
+        enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen, cursor::Hide, Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        Ok(guard)
+    }
+
+    // set once SIGINT, SIGTERM or SIGHUP arrives, the game loop should wind down.
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested.load(Ordering::Relaxed)
+    }
+
+    // blocks until any key, or until a signal asks us to stop.
+    pub fn wait_for_key(&self) {
+        while !self.quit_requested() {
+            if poll(Duration::from_millis(100)).unwrap() {
+                if let Event::Key(key_event) = read().unwrap() {
+                    if key_event.kind == KeyEventKind::Press {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+        unregister_signals(&self.signal_ids);
+    }
+}
+
+// best effort, there is nothing left to report errors to.
+fn restore() {
+    let _ = execute!(stdout(), cursor::Show, LeaveAlternateScreen);
+    let _ = disable_raw_mode();
+}
+
+#[cfg(unix)]
+type SignalId = signal_hook::SigId;
+#[cfg(not(unix))]
+type SignalId = ();
+
+// the ids are handed back on drop, so a later guard does not set a flag nobody reads.
+#[cfg(unix)]
+fn register_signals(quit_requested: &Arc<AtomicBool>) -> io::Result<Vec<SignalId>> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+    let mut signal_ids = vec![];
+    for signal in [SIGINT, SIGTERM, SIGHUP] {
+        match signal_hook::flag::register(signal, Arc::clone(quit_requested)) {
+            Ok(signal_id) => signal_ids.push(signal_id),
+            Err(err) => {
+                unregister_signals(&signal_ids);
+                return Err(err);
+            }
+        }
+    }
+    Ok(signal_ids)
+}
+
+#[cfg(unix)]
+fn unregister_signals(signal_ids: &[SignalId]) {
+    for signal_id in signal_ids {
+        signal_hook::low_level::unregister(*signal_id);
+    }
+}
+
+#[cfg(not(unix))]
+fn register_signals(_quit_requested: &Arc<AtomicBool>) -> io::Result<Vec<SignalId>> {
+    Ok(vec![])
+}
+
+#[cfg(not(unix))]
+fn unregister_signals(_signal_ids: &[SignalId]) {}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::env;
+
+use crossterm::style::{Color, Stylize};
+use serde::Deserialize;
+
+use crate::models::cell::{Cell, PieceKind};
+
+const BUILTIN_THEMES: &str = include_str!("themes.toml");
+const BASE_THEME_NAME: &str = "classic";
+const MONOCHROME_THEME_NAME: &str = "monochrome";
+
+const COLOUR_KEYS: [&str; 16] = [
+    "empty", "background", "ghost", "hint", "garbage", "i", "o", "t", "s", "z", "j", "l", "frame", "frame_bg", "text",
+    "text_bg",
+];
+const GLYPH_KEYS: [&str; 6] =
+    ["block_glyph", "ghost_glyph", "hint_glyph", "garbage_glyph", "empty_glyph", "background_glyph"];
+
+// a theme as written in toml, key -> colour or glyph, plus an optional `extends`.
+type ThemeDef = HashMap<String, String>;
+
+// how many colours the terminal can show.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum ColourDepth {
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "truecolor")]
+    Truecolor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+    #[serde(rename = "mono")]
+    Mono,
+}
+
+// the [theme] table of the config file.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub name: Option<String>,
+    pub colour_depth: ColourDepth,
+    pub themes: HashMap<String, ThemeDef>,
+}
+
+// anything that can be drawn in a grid square, ghosts and hints only ever exist on screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Empty,
+    Background,
+    Ghost,
+    Hint,
+    // a cell an opener still wants filled, in the colour of its piece.
+    Target(PieceKind),
+    Garbage,
+    Piece(PieceKind),
+}
+
+impl From<Cell> for Tile {
+    fn from(cell: Cell) -> Tile {
+        match cell {
+            Cell::Empty => Tile::Empty,
+            Cell::Garbage => Tile::Garbage,
+            Cell::Piece(kind) => Tile::Piece(kind),
+        }
+    }
+}
+
+// a resolved theme, colours already downgraded to the terminal's colour depth.
+pub struct Theme {
+    pub empty: Color,
+    pub background: Color,
+    pub ghost: Color,
+    pub hint: Color,
+    pub garbage: Color,
+    pub i: Color,
+    pub o: Color,
+    pub t: Color,
+    pub s: Color,
+    pub z: Color,
+    pub j: Color,
+    pub l: Color,
+    pub frame: Color,
+    pub frame_bg: Color,
+    pub text: Color,
+    pub text_bg: Color,
+    pub block_glyph: String,
+    pub ghost_glyph: String,
+    pub hint_glyph: String,
+    pub garbage_glyph: String,
+    pub empty_glyph: String,
+    pub background_glyph: String,
+    depth: ColourDepth,
+}
+
+impl Theme {
+    pub fn from_config(theme_config: &ThemeConfig) -> Result<Theme, String> {
+        let mut themes: HashMap<String, ThemeDef> = toml::from_str(BUILTIN_THEMES).unwrap();
+        // a user theme with a built-in name is layered over the built-in one.
+        for (name, def) in &theme_config.themes {
+            themes.entry(name.clone()).or_default().extend(def.clone());
+        }
+
+        let depth = match theme_config.colour_depth {
+            ColourDepth::Auto => detect_colour_depth(),
+            depth => depth,
+        };
+        // without colour the cells can only be told apart by their glyphs.
+        let name = match depth {
+            ColourDepth::Mono => MONOCHROME_THEME_NAME,
+            _ => theme_config.name.as_deref().unwrap_or(BASE_THEME_NAME),
+        };
+        let def = resolve_theme_def(&themes, name)?;
+
+        let value = |key: &str| -> Result<&String, String> {
+            def.get(key).ok_or_else(|| format!("theme {:?}: missing key {:?}", name, key))
+        };
+        let colour = |key: &str| -> Result<Color, String> {
+            let colour = parse_colour(value(key)?).map_err(|err| format!("theme {:?}, {}: {}", name, key, err))?;
+            Ok(downgrade_colour(colour, depth))
+        };
+        let glyph = |key: &str| -> Result<String, String> {
+            let glyph = value(key)?.clone();
+            if glyph.chars().count() != 2 {
+                return Err(format!("theme {:?}, {}: glyphs must be 2 characters wide, got {:?}", name, key, glyph));
+            }
+            Ok(glyph)
+        };
+
+        Ok(Theme {
+            empty: colour("empty")?,
+            background: colour("background")?,
+            ghost: colour("ghost")?,
+            hint: colour("hint")?,
+            garbage: colour("garbage")?,
+            i: colour("i")?,
+            o: colour("o")?,
+            t: colour("t")?,
+            s: colour("s")?,
+            z: colour("z")?,
+            j: colour("j")?,
+            l: colour("l")?,
+            frame: colour("frame")?,
+            frame_bg: colour("frame_bg")?,
+            text: colour("text")?,
+            text_bg: colour("text_bg")?,
+            block_glyph: glyph("block_glyph")?,
+            ghost_glyph: glyph("ghost_glyph")?,
+            hint_glyph: glyph("hint_glyph")?,
+            garbage_glyph: glyph("garbage_glyph")?,
+            empty_glyph: glyph("empty_glyph")?,
+            background_glyph: glyph("background_glyph")?,
+            depth,
+        })
+    }
+
+    pub fn piece(&self, kind: PieceKind) -> Color {
+        match kind {
+            PieceKind::I => self.i,
+            PieceKind::O => self.o,
+            PieceKind::T => self.t,
+            PieceKind::S => self.s,
+            PieceKind::Z => self.z,
+            PieceKind::J => self.j,
+            PieceKind::L => self.l,
+        }
+    }
+
+    pub fn paint(&self, text: &str, fg: Color, bg: Option<Color>) -> String {
+        if self.depth == ColourDepth::Mono {
+            return text.to_string();
+        }
+        match bg {
+            Some(bg) => format!("{}", text.with(fg).on(bg)),
+            None => format!("{}", text.with(fg)),
+        }
+    }
+
+    pub fn paint_bold(&self, text: &str, fg: Color, bg: Option<Color>) -> String {
+        if self.depth == ColourDepth::Mono {
+            return format!("{}", text.bold());
+        }
+        match bg {
+            Some(bg) => format!("{}", text.with(fg).on(bg).bold()),
+            None => format!("{}", text.with(fg).bold()),
+        }
+    }
+}
+
+// flattens the `extends` chain, every theme implicitly builds on the base theme.
+fn resolve_theme_def(themes: &HashMap<String, ThemeDef>, name: &str) -> Result<ThemeDef, String> {
+    let mut chain: Vec<&str> = vec![];
+    let mut cur = name;
+    loop {
+        if chain.contains(&cur) {
+            return Err(format!("theme {:?} extends itself", cur));
+        }
+        let def = themes.get(cur).ok_or_else(|| format!("unknown theme {:?}", cur))?;
+        chain.push(cur);
+        match def.get("extends") {
+            Some(parent) => cur = parent,
+            None => break,
+        }
+    }
+    if !chain.contains(&BASE_THEME_NAME) {
+        chain.push(BASE_THEME_NAME);
+    }
+
+    let mut resolved = ThemeDef::new();
+    for theme_name in chain.iter().rev() {
+        for (key, value) in &themes[*theme_name] {
+            resolved.insert(key.clone(), value.clone());
+        }
+    }
+    resolved.remove("extends");
+
+    for key in resolved.keys() {
+        if !COLOUR_KEYS.contains(&key.as_str()) && !GLYPH_KEYS.contains(&key.as_str()) {
+            return Err(format!("theme {:?}: unknown key {:?}", name, key));
+        }
+    }
+    for key in COLOUR_KEYS.iter().chain(GLYPH_KEYS.iter()) {
+        if !resolved.contains_key(*key) {
+            return Err(format!("theme {:?}: missing key {:?}", name, key));
+        }
+    }
+    Ok(resolved)
+}
+
+// best guess from the environment, there is no reliable way to query the terminal.
+fn detect_colour_depth() -> ColourDepth {
+    if env::var_os("NO_COLOR").is_some_and(|val| !val.is_empty()) {
+        return ColourDepth::Mono;
+    }
+    if let Ok(colorterm) = env::var("COLORTERM") {
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColourDepth::Truecolor;
+        }
+    }
+    match env::var("TERM") {
+        Ok(term) if term == "dumb" => ColourDepth::Mono,
+        Ok(term) if term.contains("256color") => ColourDepth::Ansi256,
+        _ => ColourDepth::Ansi16,
+    }
+}
+
+// names follow the usual ansi names, `bright_*` being the high intensity half.
+fn parse_colour(spec: &str) -> Result<Color, String> {
+    if let Some(hex) = spec.strip_prefix('#') {
+        let channel = |range: std::ops::Range<usize>| hex.get(range).and_then(|val| u8::from_str_radix(val, 16).ok());
+        return match (hex.len(), channel(0..2), channel(2..4), channel(4..6)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb { r, g, b }),
+            _ => Err(format!("bad rgb colour {:?}, expected #rrggbb", spec)),
+        };
+    }
+    if let Some(index) = spec.strip_prefix("ansi:") {
+        return index
+            .parse::<u8>()
+            .map(Color::AnsiValue)
+            .map_err(|_| format!("bad ansi colour {:?}, expected ansi:0 to ansi:255", spec));
+    }
+    match spec {
+        "default" => Ok(Color::Reset),
+        "black" => Ok(Color::Black),
+        "red" => Ok(Color::DarkRed),
+        "green" => Ok(Color::DarkGreen),
+        "yellow" => Ok(Color::DarkYellow),
+        "blue" => Ok(Color::DarkBlue),
+        "magenta" | "purple" => Ok(Color::DarkMagenta),
+        "cyan" => Ok(Color::DarkCyan),
+        "white" => Ok(Color::Grey),
+        "dark_grey" | "bright_black" => Ok(Color::DarkGrey),
+        "bright_red" => Ok(Color::Red),
+        "bright_green" => Ok(Color::Green),
+        "bright_yellow" => Ok(Color::Yellow),
+        "bright_blue" => Ok(Color::Blue),
+        "bright_magenta" => Ok(Color::Magenta),
+        "bright_cyan" => Ok(Color::Cyan),
+        "bright_white" => Ok(Color::White),
+        _ => Err(format!("unknown colour {:?}", spec)),
+    }
+}
+
+// xterm's defaults for the 16 named colours, in ansi index order.
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn downgrade_colour(colour: Color, depth: ColourDepth) -> Color {
+    match (depth, colour) {
+        (ColourDepth::Mono, _) => Color::Reset,
+        (ColourDepth::Ansi256, Color::Rgb { r, g, b }) => Color::AnsiValue(rgb_to_ansi256((r, g, b))),
+        (ColourDepth::Ansi16, Color::Rgb { .. } | Color::AnsiValue(_)) => match colour_to_rgb(colour) {
+            Some(rgb) => nearest_ansi16(rgb),
+            None => colour,
+        },
+        (_, colour) => colour,
+    }
+}
+
+fn colour_to_rgb(colour: Color) -> Option<(u8, u8, u8)> {
+    match colour {
+        Color::Rgb { r, g, b } => Some((r, g, b)),
+        Color::AnsiValue(index @ 0..=15) => Some(ANSI_16[index as usize].1),
+        Color::AnsiValue(index @ 16..=231) => {
+            let cube = index - 16;
+            Some((
+                CUBE_LEVELS[(cube / 36) as usize],
+                CUBE_LEVELS[(cube / 6 % 6) as usize],
+                CUBE_LEVELS[(cube % 6) as usize],
+            ))
+        }
+        Color::AnsiValue(index) => {
+            let level = 8 + 10 * (index - 232);
+            Some((level, level, level))
+        }
+        named => ANSI_16.iter().find(|(ansi, _)| *ansi == named).map(|(_, rgb)| *rgb),
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    dr * dr + dg * dg + db * db
+}
+
+fn rgb_to_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let nearest_level = |val: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|index| (CUBE_LEVELS[*index] as i32 - val as i32).abs())
+            .unwrap() as u8
+    };
+    let cube_index = 16 + 36 * nearest_level(rgb.0) + 6 * nearest_level(rgb.1) + nearest_level(rgb.2);
+
+    let grey = ((rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3) as u8;
+    let grey_index = 232 + (grey.saturating_sub(8) / 10).min(23);
+
+    let cube_distance = distance(rgb, colour_to_rgb(Color::AnsiValue(cube_index)).unwrap());
+    let grey_distance = distance(rgb, colour_to_rgb(Color::AnsiValue(grey_index)).unwrap());
+    if grey_distance < cube_distance {
+        grey_index
+    } else {
+        cube_index
+    }
+}
+
+fn nearest_ansi16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16.iter().min_by_key(|(_, ansi_rgb)| distance(rgb, *ansi_rgb)).unwrap().0
+}
@@ -0,0 +1,264 @@
+use std::io::Stdout;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{poll, read, Event, KeyEventKind};
+use crossterm::terminal::size as terminal_size;
+use serde::Deserialize;
+
+use crate::bindings::{Action, Bindings, BindingsConfig, Preset};
+use crate::config::Config;
+use crate::game::Game;
+use crate::models::cell::Cell;
+use crate::net::{self, Message, Peer};
+use crate::render::{mini_board_width, render_frames, render_garbage_meter, render_grid, render_message, render_mini_board, render_panel, Layout};
+use crate::terminal::TerminalGuard;
+use crate::theme::Theme;
+
+// pings go out this often, a peer quiet for longer than the timeout is gone.
+const PING_MS: u64 = 1000;
+const TIMEOUT_MS: u64 = 5000;
+
+// the [versus] table, each player's keys work like [bindings].
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VersusConfig {
+    // both players get the same pieces.
+    pub same_seed: bool,
+    pub left: BindingsConfig,
+    pub right: BindingsConfig,
+}
+
+impl Default for VersusConfig {
+    fn default() -> Self {
+        VersusConfig {
+            same_seed: false,
+            left: BindingsConfig {
+                preset: Preset::SharedLeft,
+                ..BindingsConfig::default()
+            },
+            right: BindingsConfig {
+                preset: Preset::SharedRight,
+                ..BindingsConfig::default()
+            },
+        }
+    }
+}
+
+pub struct Player {
+    pub game: Game,
+    pub bindings: Bindings,
+}
+
+// the seeds of the left and right player.
+pub fn seeds(versus: &VersusConfig, seed: u64) -> [u64; 2] {
+    match versus.same_seed {
+        true => [seed, seed],
+        false => [seed, seed.wrapping_add(1)],
+    }
+}
+
+// lines either player sent go to the other one.
+pub fn trade_garbage(left: &mut Game, right: &mut Game) {
+    let sent = left.take_sent();
+    right.receive(sent);
+    let sent = right.take_sent();
+    left.receive(sent);
+}
+
+// two players on one keyboard until one of them tops out or someone quits.
+pub fn run_local(stdout: &mut Stdout, terminal: &TerminalGuard, theme: &Theme, config: &Config, players: &mut [Player; 2]) {
+    let ruleset = &config.ruleset;
+    let duration = Duration::from_millis(config.handling.frame_ms);
+    let (term_cols, term_rows) = terminal_size().unwrap();
+    let mut layouts = Layout::split(term_cols, term_rows, ruleset.height, ruleset.width, 2);
+    render_frames(stdout, theme, &layouts);
+    let mut last_tick = Instant::now();
+
+    loop {
+        if terminal.quit_requested() {
+            return;
+        }
+        for (player, layout) in players.iter().zip(&layouts) {
+            let game = &player.game;
+            render_panel(stdout, theme, layout, game);
+            render_grid(stdout, theme, layout, &game.grid, &game.cur_tetris, None);
+            render_garbage_meter(stdout, theme, layout, game.garbage.total());
+        }
+        if players.iter().any(|player| player.game.game_over) {
+            let draw = players.iter().all(|player| player.game.game_over);
+            for (player, layout) in players.iter().zip(&layouts) {
+                let result = match (draw, player.game.game_over) {
+                    (true, _) => "D R A W",
+                    (false, true) => "G A M E  O V E R",
+                    (false, false) => "W I N S",
+                };
+                render_message(stdout, theme, layout, &[result, "", "press any key"]);
+            }
+            terminal.wait_for_key();
+            return;
+        }
+
+        if poll(duration).unwrap() {
+            match read().unwrap() {
+                Event::Resize(term_cols, term_rows) => {
+                    layouts = Layout::split(term_cols, term_rows, ruleset.height, ruleset.width, 2);
+                    render_frames(stdout, theme, &layouts);
+                }
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    // a key bound for both players goes to the left one.
+                    let pressed = players
+                        .iter_mut()
+                        .find_map(|player| player.bindings.action(&key_event).map(|action| (player, action)));
+                    match pressed {
+                        Some((_, Action::Quit)) => return,
+                        Some((player, action)) if !layouts[0].too_small => {
+                            if let Some(input) = action.input() {
+                                player.game.input(input);
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let elapsed_millis = last_tick.elapsed().as_millis() as u64;
+        last_tick += Duration::from_millis(elapsed_millis);
+        if !layouts[0].too_small {
+            for player in players.iter_mut() {
+                player.game.tick(elapsed_millis);
+            }
+        }
+        let [left, right] = players;
+        trade_garbage(&mut left.game, &mut right.game);
+    }
+}
+
+// how a game against a player over the network ended.
+pub enum Outcome {
+    Won,
+    Lost,
+    Quit,
+    Disconnected(String),
+}
+
+// your board against one player over tcp, theirs is drawn small next to it.
+pub fn run_online(stdout: &mut Stdout, terminal: &TerminalGuard, theme: &Theme, config: &Config, player: &mut Player, peer: &mut Peer) -> Outcome {
+    let ruleset = &config.ruleset;
+    let duration = Duration::from_millis(config.handling.frame_ms);
+    let (term_cols, term_rows) = terminal_size().unwrap();
+    let mut layout = online_layout(term_cols, term_rows, ruleset.height, ruleset.width);
+    render_frames(stdout, theme, &[layout]);
+    let mut last_tick = Instant::now();
+
+    let mut opponent = vec![vec![Cell::Empty; ruleset.width]; ruleset.height];
+    let mut sent_rows = vec![];
+    let mut ping_ms = None;
+    let mut last_ping = 0;
+    let mut last_heard = peer.clock();
+
+    loop {
+        if terminal.quit_requested() {
+            return Outcome::Quit;
+        }
+        let game = &mut player.game;
+        render_panel(stdout, theme, &layout, game);
+        render_grid(stdout, theme, &layout, &game.grid, &game.cur_tetris, None);
+        render_garbage_meter(stdout, theme, &layout, game.garbage.total());
+        let mini_x = layout.frame_x + layout.frame_width + 1;
+        if !layout.too_small && mini_x + mini_board_width(ruleset.width) <= layout.term_cols {
+            let label = match ping_ms {
+                Some(ping_ms) => format!("{}ms", ping_ms),
+                None => String::new(),
+            };
+            render_mini_board(stdout, theme, mini_x, layout.grid_y, &label, &opponent);
+        }
+
+        // game over and garbage first, the board can wait for the next frame.
+        let sent = game.take_sent();
+        if sent > 0 {
+            if let Err(err) = peer.send(&Message::Garbage { lines: sent }) {
+                return Outcome::Disconnected(err);
+            }
+        }
+        if game.game_over {
+            let _ = peer.send(&Message::GameOver);
+            render_message(stdout, theme, &layout, &["G A M E  O V E R", "", "press any key"]);
+            terminal.wait_for_key();
+            return Outcome::Lost;
+        }
+        let rows = net::board_rows(game);
+        if rows != sent_rows {
+            if let Err(err) = peer.send(&Message::Board { rows: rows.clone() }) {
+                return Outcome::Disconnected(err);
+            }
+            sent_rows = rows;
+        }
+        let now = peer.clock();
+        if now >= last_ping + PING_MS {
+            if let Err(err) = peer.send(&Message::Ping { millis: now }) {
+                return Outcome::Disconnected(err);
+            }
+            last_ping = now;
+        }
+
+        let messages = match peer.poll() {
+            Ok(messages) => messages,
+            Err(err) => return Outcome::Disconnected(err),
+        };
+        if !messages.is_empty() {
+            last_heard = now;
+        } else if now > last_heard + TIMEOUT_MS {
+            return Outcome::Disconnected(format!("nothing heard for {}ms", TIMEOUT_MS));
+        }
+        for message in messages {
+            match message {
+                Message::Board { rows } => opponent = net::parse_rows(&rows),
+                Message::Garbage { lines } => game.receive(lines),
+                Message::GameOver => {
+                    render_message(stdout, theme, &layout, &["W I N S", "", "press any key"]);
+                    terminal.wait_for_key();
+                    return Outcome::Won;
+                }
+                Message::Ping { millis } => {
+                    if let Err(err) = peer.send(&Message::Pong { millis }) {
+                        return Outcome::Disconnected(err);
+                    }
+                }
+                Message::Pong { millis } => ping_ms = Some(now.saturating_sub(millis)),
+                Message::Bye => return Outcome::Disconnected("the other player left".to_string()),
+                Message::Hello { .. } => (),
+            }
+        }
+
+        if poll(duration).unwrap() {
+            match read().unwrap() {
+                Event::Resize(term_cols, term_rows) => {
+                    layout = online_layout(term_cols, term_rows, ruleset.height, ruleset.width);
+                    render_frames(stdout, theme, &[layout]);
+                }
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => match player.bindings.action(&key_event) {
+                    Some(Action::Quit) => return Outcome::Quit,
+                    Some(action) if !layout.too_small => {
+                        if let Some(input) = action.input() {
+                            game.input(input);
+                        }
+                    }
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+
+        // the other player keeps going, so the game does not wait for a small terminal.
+        let elapsed_millis = last_tick.elapsed().as_millis() as u64;
+        last_tick += Duration::from_millis(elapsed_millis);
+        game.tick(elapsed_millis);
+    }
+}
+
+// the frame with room for the mini board next to it.
+fn online_layout(term_cols: u16, term_rows: u16, num_rows: usize, num_cols: usize) -> Layout {
+    Layout::with_reserved(term_cols, term_rows, num_rows, num_cols, mini_board_width(num_cols) + 1)
+}
@@ -0,0 +1,68 @@
+/* builds against include/tetri_rs.h and the cdylib, see the Makefile next to it. */
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "tetri_rs.h"
+
+#define CHECK(expr, expected)                                                         \
+    do {                                                                              \
+        TetriStatus status = (expr);                                                  \
+        if (status != (expected)) {                                                   \
+            fprintf(stderr, "%s:%d: %s gave %d, expected %d\n", __FILE__, __LINE__, #expr, \
+                    (int)status, (int)(expected));                                    \
+            exit(1);                                                                  \
+        }                                                                             \
+    } while (0)
+
+int main(void) {
+    if (tetri_abi_version() != TETRI_ABI_VERSION) {
+        fprintf(stderr, "abi version %u, header is %d\n", tetri_abi_version(), TETRI_ABI_VERSION);
+        return 1;
+    }
+
+    TetriGame *game = NULL;
+    CHECK(tetri_game_new(1, 3, 20, &game), TETRI_INVALID_ARGUMENT);
+    CHECK(tetri_game_new(1, 10, 20, NULL), TETRI_NULL_POINTER);
+    CHECK(tetri_game_new(1, 10, 20, &game), TETRI_OK);
+
+    uint32_t width = 0, height = 0;
+    CHECK(tetri_game_size(game, &width, &height), TETRI_OK);
+    if (width != 10 || height != 20) {
+        fprintf(stderr, "size %ux%u\n", width, height);
+        return 1;
+    }
+
+    TetriPiece piece;
+    CHECK(tetri_game_piece(game, &piece), TETRI_OK);
+    CHECK(tetri_game_input(game, 99), TETRI_INVALID_ARGUMENT);
+    CHECK(tetri_game_input(NULL, TETRI_INPUT_HARD_DROP), TETRI_NULL_POINTER);
+
+    /* the first piece lands on the floor. */
+    CHECK(tetri_game_input(game, TETRI_INPUT_HARD_DROP), TETRI_OK);
+    uint8_t cells[10 * 20];
+    CHECK(tetri_game_board(game, cells, 10), TETRI_BUFFER_TOO_SMALL);
+    CHECK(tetri_game_board(game, cells, sizeof cells), TETRI_OK);
+    int filled = 0;
+    for (size_t i = 0; i < sizeof cells; i++) {
+        if (cells[i] == piece.kind) {
+            filled++;
+        }
+    }
+    if (filled != 4) {
+        fprintf(stderr, "%d cells of the dropped piece\n", filled);
+        return 1;
+    }
+
+    /* stacking in one column tops out. */
+    TetriState state;
+    CHECK(tetri_game_state(game, &state), TETRI_OK);
+    while (!state.game_over) {
+        CHECK(tetri_game_tick(game, 1000), TETRI_OK);
+        CHECK(tetri_game_state(game, &state), TETRI_OK);
+    }
+    CHECK(tetri_game_input(game, TETRI_INPUT_HARD_DROP), TETRI_GAME_OVER);
+    printf("ok, %u pieces\n", state.pieces);
+
+    tetri_game_free(game);
+    return 0;
+}